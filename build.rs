@@ -0,0 +1,17 @@
+// Generates a test per data/dayNN_example*.txt, so each example can be run, and fail, on its own.
+// The tests are included by the fixtures module's tests; see src/fixtures.rs.
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=data");
+    let mut names: Vec<String> = std::fs::read_dir("data").expect("Failed to read data/")
+        .map(|entry| entry.expect("Failed to read data/").file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("day") && name.contains("_example") && name.ends_with(".txt"))
+        .collect();
+    names.sort();
+    let tests: String = names.iter()
+        .map(|name| format!("#[test]\nfn {}() {{\n    super::check_example({:?});\n}}\n", name.trim_end_matches(".txt"), name))
+        .collect();
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    std::fs::write(Path::new(&out_dir).join("fixture_tests.rs"), tests).expect("Failed to write fixture tests");
+}
@@ -0,0 +1,203 @@
+use crate::solution::Answer;
+use anyhow::{Context, Result};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+// put() reads, updates and replaces a whole file, so concurrent calls (e.g. batch --jobs) would
+// drop each other's answers
+static WRITING: Mutex<()> = Mutex::new(());
+// Distinguishes each put()'s temporary file
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Stops answers being read from or written to the cache for the rest of the run.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+// FNV-1a, which unlike std's DefaultHasher is stable across Rust releases
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 { self.0 }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn hash(parts: &[&[u8]]) -> String {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    for part in parts {
+        hasher.write(part);
+        hasher.write(&[0]); // so ["ab", "c"] and ["a", "bc"] differ
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// Identifies what a day is being asked to solve: its input and any options it was given.
+pub fn input_key(input: &str, args: &[String]) -> String {
+    let mut parts = vec!(input.as_bytes());
+    parts.extend(args.iter().map(|a| a.as_bytes()));
+    hash(&parts)
+}
+
+/// Stores each day's answers in `DIR/dayNN.txt`, one `BUILD INPUT_KEY PART ANSWER` line per
+/// answer. Only answers from the current build are used; older ones are dropped when the file is
+/// next written.
+pub struct AnswerCache {
+    dir: PathBuf,
+    build: String,
+}
+
+impl AnswerCache {
+    pub fn new(dir: &Path, build: &str) -> AnswerCache {
+        AnswerCache{ dir: dir.to_path_buf(), build: build.to_string() }
+    }
+
+    /// The cache for the running binary, stored next to it and keyed by its size and modification
+    /// time, so any rebuild starts afresh. None if caching is disabled or the binary can't be found.
+    pub fn open() -> Option<AnswerCache> {
+        if !ENABLED.load(Ordering::SeqCst) { return None; }
+        let exe = std::env::current_exe().ok()?;
+        let metadata = exe.metadata().ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        let build = hash(&[&metadata.len().to_le_bytes(), &modified.to_le_bytes()]);
+        Some(AnswerCache::new(&exe.parent()?.join("answers"), &build))
+    }
+
+    fn path(&self, day: u32) -> PathBuf {
+        self.dir.join(format!("day{:02}.txt", day))
+    }
+
+    // The lines for this build, split into their input key, part and encoded answer
+    fn entries(&self, day: u32) -> Vec<(String, u32, String)> {
+        let contents = std::fs::read_to_string(self.path(day)).unwrap_or_default();
+        contents.lines().filter_map(|line| {
+            let mut parts = line.splitn(4, ' ');
+            let (build, key, part, answer) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+            if build != self.build { return None; }
+            Some((key.to_string(), part.parse().ok()?, answer.to_string()))
+        }).collect()
+    }
+
+    pub fn get(&self, day: u32, part: u32, key: &str) -> Option<Answer> {
+        self.entries(day).into_iter()
+            .find(|(k, p, _)| k == key && *p == part)
+            .and_then(|(_, _, answer)| decode(&answer))
+    }
+
+    pub fn put(&self, day: u32, part: u32, key: &str, answer: &Answer) -> Result<()> {
+        let _lock = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+        let mut lines: Vec<_> = self.entries(day).into_iter()
+            .filter(|(k, p, _)| !(k == key && *p == part))
+            .map(|(k, p, a)| format!("{} {} {} {}", self.build, k, p, a))
+            .collect();
+        lines.push(format!("{} {} {} {}", self.build, key, part, encode(answer)));
+
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let path = self.path(day);
+        // Written to a temporary file first, so a concurrent reader never sees a partial file
+        let temp = self.dir.join(format!(".day{:02}.txt.{}.{}.tmp",
+                                         day, std::process::id(), WRITES.fetch_add(1, Ordering::SeqCst)));
+        std::fs::write(&temp, lines.join("\n") + "\n").with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, &path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn encode(answer: &Answer) -> String {
+    match answer {
+        Answer::Int(n) => format!("int {}", n),
+        Answer::Text(s) => format!("text {}", s.replace('\\', "\\\\").replace('\n', "\\n")),
+        Answer::None => "none".to_string(),
+    }
+}
+
+fn decode(encoded: &str) -> Option<Answer> {
+    match encoded.split_once(' ') {
+        Some(("int", n)) => n.parse().ok().map(Answer::Int),
+        Some(("text", s)) => {
+            let mut text = String::new();
+            let mut chars = s.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    text.push(if chars.next()? == 'n' { '\n' } else { '\\' });
+                } else {
+                    text.push(c);
+                }
+            }
+            Some(Answer::Text(text))
+        },
+        None if encoded == "none" => Some(Answer::None),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    parameterized_test::create!{round_trip, answer, {
+        assert_eq!(decode(&encode(&answer)), Some(answer));
+    }}
+    round_trip!{
+        int: Answer::Int(-12345),
+        text: Answer::Text("abc".into()),
+        escaped: Answer::Text("a b\\n\nc".into()),
+        none: Answer::None,
+    }
+
+    #[test]
+    fn keys() {
+        let args = vec!("--rounds".to_string(), "10".to_string());
+        assert_eq!(input_key("389125467", &args), input_key("389125467", &args));
+        assert_ne!(input_key("389125467", &args), input_key("389125467", &[]));
+        assert_ne!(input_key("389125467", &[]), input_key("389125468", &[]));
+    }
+
+    #[test]
+    fn cache() {
+        let dir = std::env::temp_dir().join(format!("advent-2020-answers-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = AnswerCache::new(&dir, "build1");
+        assert_eq!(cache.get(23, 1, "input"), None);
+
+        cache.put(23, 1, "input", &Answer::Text("67384529".into())).unwrap();
+        cache.put(23, 2, "input", &Answer::Int(149245887792)).unwrap();
+        cache.put(23, 2, "other", &Answer::Int(5)).unwrap();
+        cache.put(23, 2, "input", &Answer::Int(149245887793)).unwrap();
+        assert_eq!(cache.get(23, 1, "input"), Some(Answer::Text("67384529".into())));
+        assert_eq!(cache.get(23, 2, "input"), Some(Answer::Int(149245887793)));
+        assert_eq!(cache.get(23, 2, "other"), Some(Answer::Int(5)));
+        assert_eq!(cache.get(24, 1, "input"), None);
+
+        // A new build ignores, and eventually replaces, the old build's answers
+        let rebuilt = AnswerCache::new(&dir, "build2");
+        assert_eq!(rebuilt.get(23, 1, "input"), None);
+        rebuilt.put(23, 1, "input", &Answer::None).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("day23.txt")).unwrap(), "build2 input 1 none\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_puts() {
+        let dir = std::env::temp_dir().join(format!("advent-2020-concurrent-answers-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = AnswerCache::new(&dir, "build");
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || cache.put(1, 1, &format!("input{}", i), &Answer::Int(i)).unwrap());
+            }
+        });
+        for i in 0..8 {
+            assert_eq!(cache.get(1, 1, &format!("input{}", i)), Some(Answer::Int(i)));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,32 +1,108 @@
-pub fn advent() {
-    let data = parse_data();
-    let (a, b) = find_pair(&data).expect("No result");
-    println!("{}*{} = {}", a, b, a*b);
-    let (a, b, c) = find_triple(&data).expect("No result");
-    println!("{}*{}*{} = {}", a, b, c, a*b*c);
+use std::convert::TryFrom;
+use anyhow::{ensure, Context, Result};
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
+
+pub struct Expenses {
+    entries: Vec<u32>,
+    target: u32,
 }
 
-fn parse_data() -> Vec<u32> {
-    return include_str!("../data/day01.txt").lines().map(|l| l.parse::<u32>().unwrap()).collect();
+impl Solution for Expenses {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Expenses{ entries: parse_data(input)?, target: 2020 })
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "target", kind: Kind::Number{ min: 0 }, default: Some("2020"),
+               help: "The sum the entries must add up to" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(target) = options.number("target") {
+            self.target = u32::try_from(target).context("--target is too large")?;
+        }
+        Ok(())
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let (a, b) = find_pair(&self.entries, self.target).context("No result").map_err(Failure::no_solution)?;
+        let product = a as u64 * b as u64;
+        Console::detail(format!("{}*{} = {}", a, b, product));
+        Ok(product.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let (a, b, c) = find_triple(&self.entries, self.target).context("No result").map_err(Failure::no_solution)?;
+        let product = (a as u64 * b as u64).checked_mul(c as u64)
+            .context("Product is too large").map_err(Failure::no_solution)?;
+        Console::detail(format!("{}*{}*{} = {}", a, b, c, product));
+        Ok(product.into())
+    }
+
+    /// Generates `size` entries.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_entries(rng, size)
+    }
 }
 
-fn find_pair(data: &[u32]) -> Option<(u32, u32)> {
-    for offset in 0..data.len()-1 {
+// Plants one pair and one triple that sum to 2020. The other entries are all over 1010, so they can
+// only complete a sum with the planted entries, which is checked.
+fn generate_entries(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((5..=500).contains(&size), "Size should be between 5 and 500 entries");
+    // Whether adding n would complete a second pair or triple (or duplicate an entry)
+    let completes_sum = |entries: &[u64], n: u64| entries.contains(&n)
+        || entries.iter().any(|&e| e + n == 2020)
+        || entries.iter().enumerate().any(|(i, &a)| entries[i+1..].iter().any(|&b| a + b + n == 2020));
+    let mut entries = loop {
+        let a = rng.range(1, 2020);
+        let x = rng.range(1, 2019);
+        let y = rng.range(1, 2020 - x);
+        let (pair, triple) = ([a, 2020 - a], [x, y, 2020 - x - y]);
+        if pair[0] != pair[1] && !completes_sum(&triple, pair[0]) && !completes_sum(&triple, pair[1])
+            && triple[0] != triple[1] && triple[1] != triple[2] && triple[0] != triple[2]
+            && !triple.iter().any(|&t| triple.iter().any(|&u| t + u == 2020)) {
+            break vec!(pair[0], pair[1], triple[0], triple[1], triple[2]);
+        }
+    };
+    let part1 = entries[0] * entries[1];
+    let part2 = entries[2] * entries[3] * entries[4];
+    while entries.len() < size {
+        let n = rng.range(1011, 2020);
+        if !completes_sum(&entries, n) { entries.push(n); }
+    }
+    rng.shuffle(&mut entries);
+    let input = entries.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: part2.into() })
+}
+
+fn parse_data(input: &str) -> Result<Vec<u32>> {
+    let entries: Vec<_> = input.lines().map(|l| l.parse::<u32>()).collect::<Result<_, _>>()?;
+    // Part 2 needs three entries to sum
+    ensure!(entries.len() >= 3, "Expected at least 3 entries, found {}", entries.len());
+    Ok(entries)
+}
+
+// Sums are widened so large entries can't overflow
+fn find_pair(data: &[u32], target: u32) -> Option<(u32, u32)> {
+    for offset in 0..data.len().saturating_sub(1) {
         let a = data[offset];
         for b in &data[offset+1..] {
-            if a + b == 2020 { return Some((a, *b)) }
+            if a as u64 + *b as u64 == target as u64 { return Some((a, *b)) }
         }
     }
     None
 }
 
-fn find_triple(data: &[u32]) -> Option<(u32, u32, u32)> {
-    for offset_a in 0..data.len()-2 {
+fn find_triple(data: &[u32], target: u32) -> Option<(u32, u32, u32)> {
+    for offset_a in 0..data.len().saturating_sub(2) {
         let a = data[offset_a];
         for offset_b in offset_a+1..data.len()-1 {
             let b = data[offset_b];
             for c in &data[offset_b+1..] {
-                if a + b + c == 2020 { return Some((a, b, *c)) }
+                if a as u64 + b as u64 + *c as u64 == target as u64 { return Some((a, b, *c)) }
             }
         }
     }
@@ -39,12 +115,33 @@ mod tests {
 
     #[test]
     fn example() {
-        assert_eq!(find_pair(&[1721, 979, 366, 299, 675, 1456]), Some((1721, 299)));
-        assert_eq!(find_triple(&[1721, 979, 366, 299, 675, 1456]), Some((979, 366, 675)));
+        assert_eq!(find_pair(&[1721, 979, 366, 299, 675, 1456], 2020), Some((1721, 299)));
+        assert_eq!(find_triple(&[1721, 979, 366, 299, 675, 1456], 2020), Some((979, 366, 675)));
+    }
+
+    parameterized_test::create!{too_few, input, {
+        assert!(parse_data(input).is_err());
+    }}
+    too_few!{
+        empty: "",
+        one: "2020",
+        two: "1010\n1010",
+    }
+
+    #[test]
+    fn short() {
+        assert_eq!(find_pair(&[], 2020), None);
+        assert_eq!(find_triple(&[2020], 2020), None);
+        assert_eq!(find_pair(&[u32::MAX, 1], 0), None);
     }
 
     #[test]
     fn parse_file() {
-        assert!(parse_data().len() > 0);
+        assert!(parse_data(include_str!("../data/day01.txt")).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Expenses>("generated expenses", 20, 5..=100);
     }
 }
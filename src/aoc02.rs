@@ -1,11 +1,33 @@
 use std::str::FromStr;
-use anyhow::{Context, Error, Result};
+use anyhow::{ensure, Context, Error, Result};
 use crate::parsing;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let data = parse_data();
-    println!("Valid Passwords: {}", data.iter().filter(|e| e.nums_as_range()).count());
-    println!("Valid Passwords: {}", data.iter().filter(|e| e.nums_as_positions()).count());
+pub struct Passwords(Vec<Entry>);
+
+impl Solution for Passwords {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Passwords(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let valid = self.0.iter().filter(|e| e.nums_as_range()).count();
+        Console::detail(format!("Valid Passwords: {}", valid));
+        Ok(valid.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let valid = self.0.iter().filter(|e| e.nums_as_positions()).count();
+        Console::detail(format!("Valid Passwords: {}", valid));
+        Ok(valid.into())
+    }
+
+    /// Generates `size` passwords and their policies.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_entries(rng, size)
+    }
 }
 
 #[derive(Debug)]
@@ -22,8 +44,9 @@ impl Entry {
     }
 
     fn nums_as_positions(&self) -> bool {
-        let match_a = self.password.chars().nth(self.nums.0 as usize - 1).unwrap() == self.letter;
-        let match_b = self.password.chars().nth(self.nums.1 as usize - 1).unwrap() == self.letter;
+        // Positions are 1-indexed; positions outside the password don't match
+        let matches = |pos: i32| pos > 0 && self.password.chars().nth(pos as usize - 1) == Some(self.letter);
+        let (match_a, match_b) = (matches(self.nums.0), matches(self.nums.1));
         (match_a || match_b) && !(match_a && match_b) // XOR
     }
 }
@@ -41,8 +64,45 @@ impl FromStr for Entry {
     }
 }
 
-fn parse_data() -> Vec<Entry> {
-    return include_str!("../data/day02.txt").lines().map(|l| l.parse::<Entry>().unwrap()).collect();
+// Each password is built to be valid or not under each policy, chosen at random: its letter appears
+// a number of times in or out of the range, at one, neither or both of the positions.
+fn generate_entries(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 passwords");
+    let (mut part1, mut part2) = (0, 0);
+    let mut lines = Vec::new();
+    while lines.len() < size {
+        let (low, high) = (rng.range(1, 8) as usize, rng.range(8, 15) as usize);
+        let len = rng.range(high as u64, high as u64 + 6) as usize;
+        let (in_range, at_one) = (rng.chance(1, 2), rng.chance(1, 2));
+        let count = if in_range { rng.range(low as u64, high as u64 + 1) as usize }
+            else if len == high || rng.chance(1, 2) { rng.range(0, low as u64) as usize }
+            else { rng.range(high as u64 + 1, len as u64 + 1) as usize };
+        let at_positions = if at_one { 1 } else if rng.chance(1, 2) { 2 } else { 0 };
+        // The rest of the letter's occurrences have to fit in the rest of the password
+        if count < at_positions || count - at_positions > len - 2 { continue; }
+
+        let letter = (b'a' + rng.range(0, 26) as u8) as char;
+        let other = |rng: &mut Rng| loop {
+            let c = (b'a' + rng.range(0, 26) as u8) as char;
+            if c != letter { break c; }
+        };
+        let mut others: Vec<_> = (0..len).filter(|&i| i != low - 1 && i != high - 1).collect();
+        rng.shuffle(&mut others);
+        let mut password: Vec<_> = (0..len).map(|_| other(rng)).collect();
+        for &i in others.iter().take(count - at_positions) { password[i] = letter; }
+        let first = rng.chance(1, 2);
+        if at_positions == 2 || at_positions == 1 && first { password[low - 1] = letter; }
+        if at_positions == 2 || at_positions == 1 && !first { password[high - 1] = letter; }
+
+        if in_range { part1 += 1; }
+        if at_one { part2 += 1; }
+        lines.push(format!("{}-{} {}: {}", low, high, letter, password.into_iter().collect::<String>()));
+    }
+    Ok(Generated{ input: lines.join("\n") + "\n", part1: part1.into(), part2: part2.into() })
+}
+
+fn parse_data(input: &str) -> Result<Vec<Entry>> {
+    input.lines().map(|l| l.parse::<Entry>()).collect()
 }
 
 
@@ -63,6 +123,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        assert!(parse_data().len() > 0);
+        assert!(parse_data(include_str!("../data/day02.txt")).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Passwords>("generated passwords", 20, 1..=100);
     }
 }
@@ -1,19 +1,51 @@
 use std::str::FromStr;
-use anyhow::{Error, Result};
+use anyhow::{ensure, Error, Result};
 use crate::euclid::{Point,Vector,point,vector};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let landscape = parse_data();
-    let slope = vector(3, 1);
-    println!("Traversed via {} and hit {} trees", slope, landscape.traverse(slope));
+impl Solution for Landscape {
+    fn parse(input: &str) -> Result<Self> {
+        input.parse()
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let slope = vector(3, 1);
+        let trees = self.traverse(slope);
+        Console::detail(format!("Traversed via {} and hit {} trees", slope, trees));
+        Ok(trees.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let slopes = vec!(vector(1, 1), vector(3, 1), vector(5, 1), vector(7, 1), vector(1, 2));
+        let tree_product = self.traverse_multi(slopes).iter().product::<i64>();
+        Console::detail(format!("Product of trees: {}", tree_product));
+        Ok(tree_product.into())
+    }
+
+    /// Generates a landscape `size` rows tall.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_landscape(rng, size)
+    }
+}
 
-    let slopes = vec!(vector(1, 1), vector(3, 1), vector(5, 1), vector(7, 1), vector(1, 2));
-    let tree_product: i64 = landscape.traverse_multi(slopes).iter().product();
-    println!("Product of trees: {}", tree_product);
+// The trees hit are counted row by row, rather than by stepping through points like traverse()
+fn generate_landscape(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 rows");
+    let width = rng.range(5, 32) as usize;
+    let rows: Vec<Vec<bool>> = (0..size).map(|_| (0..width).map(|_| rng.chance(1, 4)).collect()).collect();
+    let hits = |right: usize, down: usize| -> i64 {
+        rows.iter().step_by(down).enumerate().filter(|(i, row)| row[i * right % width]).count() as i64
+    };
+    let input = rows.iter().map(|row| row.iter().map(|&t| if t { '#' } else { '.' }).collect::<String>())
+        .collect::<Vec<_>>().join("\n");
+    let part2 = hits(1, 1) * hits(3, 1) * hits(5, 1) * hits(7, 1) * hits(1, 2);
+    Ok(Generated{ input: input + "\n", part1: hits(3, 1).into(), part2: part2.into() })
 }
 
 #[derive(Debug)]
-struct Landscape {
+pub struct Landscape {
     trees: Vec<Vec<bool>>,
 }
 
@@ -43,18 +75,18 @@ impl FromStr for Landscape {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let mut trees = Vec::new();
-        for line in s.lines() {
+        let mut trees: Vec<Vec<bool>> = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            ensure!(line.chars().all(|c| c == '#' || c == '.'), "Line {}: expected only '#' and '.': {}", i + 1, line);
+            let width = trees.first().map(|r| r.len()).unwrap_or(line.len());
+            ensure!(line.len() == width, "Line {}: expected {} columns, found {}", i + 1, width, line.len());
             trees.push(line.chars().map(|c| c == '#').collect());
         }
+        ensure!(trees.first().map(|r| !r.is_empty()).unwrap_or(false), "No landscape");
         Ok(Landscape{trees})
     }
 }
 
-fn parse_data() -> Landscape {
-    include_str!("../data/day03.txt").parse::<Landscape>().unwrap()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,9 +114,24 @@ mod tests {
         assert_eq!(landscape.traverse_multi(slopes), vec!(2, 7, 3, 4, 2));
     }
 
+    parameterized_test::create!{bad_landscapes, input, {
+        assert!(Landscape::parse(input).is_err());
+    }}
+    bad_landscapes!{
+        empty: "",
+        blank: "\n\n",
+        ragged: "..#\n.#\n#..",
+        unexpected: "..#\n.X.",
+    }
+
     #[test]
     fn parse_file() {
         parse_example();
-        parse_data();
+        Landscape::parse(include_str!("../data/day03.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Landscape>("generated landscapes", 20, 1..=100);
     }
 }
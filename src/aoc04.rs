@@ -1,14 +1,102 @@
 use anyhow::{bail, ensure, Context, Result};
 use crate::parsing;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let passports = parse_data();
-    println!("Valid Fields: {}", passports.iter().filter(|p| valid_fields(p)).count());
-    println!("Valid Values: {}", passports.iter().filter(|p| valid_values(p).is_ok()).count());
+pub struct Passports(Vec<String>);
+
+impl Solution for Passports {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Passports(parse_data(input)))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let valid = self.0.iter().filter(|p| valid_fields(p)).count();
+        Console::detail(format!("Valid Fields: {}", valid));
+        Ok(valid.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let valid = self.0.iter().filter(|p| valid_values(p).is_ok()).count();
+        Console::detail(format!("Valid Values: {}", valid));
+        Ok(valid.into())
+    }
+
+    /// Generates `size` passports.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_passports(rng, size)
+    }
+}
+
+const REQUIRED: &[&str] = &["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+const EYE_COLORS: &[&str] = &["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
+fn digits(rng: &mut Rng, count: usize) -> String {
+    (0..count).map(|_| rng.range(0, 10).to_string()).collect()
+}
+
+// A value for the field, which is valid or not as requested
+fn field_value(rng: &mut Rng, field: &str, valid: bool) -> String {
+    let year = |rng: &mut Rng, low: u64, high: u64| if valid { rng.range(low, high + 1) }
+        else if rng.chance(1, 2) { rng.range(low - 20, low) } else { rng.range(high + 1, high + 20) };
+    match field {
+        "byr" => year(rng, 1920, 2002).to_string(),
+        "iyr" => year(rng, 2010, 2020).to_string(),
+        "eyr" => year(rng, 2020, 2030).to_string(),
+        "hgt" => match (valid, rng.range(0, if valid { 2 } else { 5 })) {
+            (true, 0) => format!("{}cm", rng.range(150, 194)),
+            (true, _) => format!("{}in", rng.range(59, 77)),
+            (false, 0) => format!("{}cm", rng.range(100, 150)),
+            (false, 1) => format!("{}cm", rng.range(194, 250)),
+            (false, 2) => format!("{}in", rng.range(40, 59)),
+            (false, 3) => format!("{}in", rng.range(77, 100)),
+            (false, _) => rng.range(150, 194).to_string(),
+        },
+        "hcl" => {
+            let hex: String = (0..6).map(|_| std::char::from_digit(rng.range(0, 16) as u32, 16).expect("< 16")).collect();
+            if valid { format!("#{}", hex) } else if rng.chance(1, 2) { hex } else { format!("#{}z", &hex[1..]) }
+        },
+        "ecl" => if valid { EYE_COLORS[rng.range(0, EYE_COLORS.len() as u64) as usize].to_string() }
+            else { ["xry", "zzz", "grt", "blue"][rng.range(0, 4) as usize].to_string() },
+        "pid" => {
+            let count = if valid { 9 } else if rng.chance(1, 2) { 8 } else { 10 };
+            digits(rng, count)
+        },
+        "cid" => rng.range(100, 350).to_string(),
+        _ => unreachable!("Unknown field {}", field),
+    }
+}
+
+// Each passport is missing a required field, has one invalid value, or is entirely valid
+fn generate_passports(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 passports");
+    let (mut part1, mut part2) = (0, 0);
+    let mut passports = Vec::new();
+    for _ in 0..size {
+        let mut fields: Vec<_> = REQUIRED.to_vec();
+        if rng.chance(1, 2) { fields.push("cid"); }
+        let kind = rng.range(0, 3);
+        let broken = rng.range(0, REQUIRED.len() as u64) as usize;
+        match kind {
+            0 => { fields.retain(|&f| f != REQUIRED[broken]); },
+            1 => part1 += 1,
+            _ => { part1 += 1; part2 += 1; },
+        }
+        rng.shuffle(&mut fields);
+        let mut passport = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 { passport.push(if rng.chance(1, 3) { '\n' } else { ' ' }); }
+            let valid = !(kind == 1 && *field == REQUIRED[broken]);
+            passport.push_str(&format!("{}:{}", field, field_value(rng, field, valid)));
+        }
+        passports.push(passport);
+    }
+    Ok(Generated{ input: passports.join("\n\n") + "\n", part1: part1.into(), part2: part2.into() })
 }
 
-fn parse_data() -> Vec<String> {
-    include_str!("../data/day04.txt").split("\n\n").map(|s| s.to_string()).collect()
+fn parse_data(input: &str) -> Vec<String> {
+    input.split("\n\n").map(|s| s.to_string()).collect()
 }
 
 fn valid_fields(p: &str) -> bool {
@@ -42,13 +130,11 @@ fn valid_values(p: &str) -> Result<()> {
     let expr_year = parsing::capture_group(&parsing::regex_captures(&eyr_regex, p)?, 1).parse::<i32>()?;
     ensure!(expr_year >= 2020 && expr_year <= 2030, "eyr");
 
-    let height_cm_capture = parsing::regex_captures(&hgt_cm_regex, p);
-    let height_in_capture = parsing::regex_captures(&hgt_in_regex, p);
-    if height_cm_capture.is_ok() {
-        let height_cm = parsing::capture_group(&height_cm_capture.unwrap(), 1).parse::<i32>()?;
+    if let Ok(height_cm_capture) = parsing::regex_captures(&hgt_cm_regex, p) {
+        let height_cm = parsing::capture_group(&height_cm_capture, 1).parse::<i32>()?;
         ensure!(height_cm >= 150 && height_cm <= 193, "hgt");
-    } else if height_in_capture.is_ok() {
-        let height_in = parsing::capture_group(&height_in_capture.unwrap(), 1).parse::<i32>()?;
+    } else if let Ok(height_in_capture) = parsing::regex_captures(&hgt_in_regex, p) {
+        let height_in = parsing::capture_group(&height_in_capture, 1).parse::<i32>()?;
         ensure!(height_in >= 59 && height_in <= 76, "hgt");
     } else {
         bail!("hgt");
@@ -98,6 +184,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        assert!(!parse_data().is_empty());
+        assert!(!parse_data(include_str!("../data/day04.txt")).is_empty());
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Passports>("generated passports", 20, 1..=100);
     }
 }
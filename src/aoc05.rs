@@ -1,25 +1,64 @@
-use std::cmp::max;
 use std::collections::BTreeSet;
+use anyhow::{anyhow, ensure, Context, Result};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let seats = parse_data();
-    let mut highest = 0;
-    for seat in seats.iter() {
-      highest = max(highest, make_id(parse(seat)));
+pub struct BoardingPasses(Vec<u32>);
+
+impl Solution for BoardingPasses {
+    fn parse(input: &str) -> Result<Self> {
+        let seats = parse_data(input).into_iter().enumerate()
+            .map(|(i, seat)| parse(seat).map(make_id).with_context(|| format!("Line {}", i + 1)))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(!seats.is_empty(), "No seats");
+        Ok(BoardingPasses(seats))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let highest = self.0.iter().copied().max().expect("Parsing ensures there are seats");
+        Console::detail(format!("Highest seat ID: {}", highest));
+        Ok(highest.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let mut candidate_seats = {0..1024}.collect::<BTreeSet<_>>();
+        for id in self.0.iter() {
+            candidate_seats.remove(id);
+        }
+        let empty_seats = strip_sequences(&candidate_seats);
+        if empty_seats.len() != 1 {
+            return Err(Failure::no_solution(anyhow!("Expected exactly one empty seat, found {:?}", empty_seats)));
+        }
+        let empty_seat = *empty_seats.iter().next().expect("len=1");
+        Console::detail(format!("Empty seat ID: {}", empty_seat));
+        Ok(empty_seat.into())
     }
-    println!("Highest seat ID: {}", highest);
 
-    let mut candidate_seats = {0..1024}.collect::<BTreeSet<_>>();
-    for seat in seats.iter() {
-        candidate_seats.remove(&make_id(parse(seat)));
+    /// Generates `size` boarding passes.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_passes(rng, size)
     }
-    let empty_seats = strip_sequences(&candidate_seats);
-    assert_eq!(empty_seats.len(), 1);
-    println!("Empty seat ID: {}", empty_seats.iter().next().unwrap());
 }
 
-fn parse_data() -> Vec<&'static str> {
-    return include_str!("../data/day05.txt").lines().collect();
+// A run of seats with one missing, which stays clear of the first and last two seats so the only
+// other empty seats are the runs before and after it
+fn generate_passes(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((2..=1000).contains(&size), "Size should be between 2 and 1000 passes");
+    let first = rng.range(2, 1022 - size as u64);
+    let last = first + size as u64;
+    let missing = rng.range(first + 1, last);
+    let mut passes: Vec<_> = (first..=last).filter(|&id| id != missing).map(|id| {
+        let row: String = (0..7).rev().map(|bit| if (id / 8) >> bit & 1 == 1 { 'B' } else { 'F' }).collect();
+        let col: String = (0..3).rev().map(|bit| if (id % 8) >> bit & 1 == 1 { 'R' } else { 'L' }).collect();
+        row + &col
+    }).collect();
+    rng.shuffle(&mut passes);
+    Ok(Generated{ input: passes.join("\n") + "\n", part1: last.into(), part2: missing.into() })
+}
+
+fn parse_data(input: &str) -> Vec<&str> {
+    return input.lines().collect();
 }
 
 fn to_num(symbol: &str, ones: char) -> u32 {
@@ -33,11 +72,13 @@ fn to_num(symbol: &str, ones: char) -> u32 {
   return num;
 }
 
-fn parse(seat: &str) -> (u32, u32) {
-  assert_eq!(seat.len(), 10);
+fn parse(seat: &str) -> Result<(u32, u32)> {
+  ensure!(seat.len() == 10 && seat.is_ascii(), "Expected 10 characters: {}", seat);
   let row = &seat[..7];
   let col = &seat[7..];
-  return (to_num(row, 'B'), to_num(col, 'R'));
+  ensure!(row.chars().all(|c| c == 'F' || c == 'B') && col.chars().all(|c| c == 'L' || c == 'R'),
+          "Expected seven F or B followed by three L or R: {}", seat);
+  return Ok((to_num(row, 'B'), to_num(col, 'R')));
 }
 
 fn make_id(seat: (u32, u32)) -> u32 { seat.0 * 8 + seat.1 }
@@ -57,7 +98,7 @@ mod tests {
     use super::*;
 
     parameterized_test::create!{seats, (code, seat, id), {
-      let decoded = parse(code);
+      let decoded = parse(code).unwrap();
       assert_eq!(decoded, seat);
       assert_eq!(make_id(decoded), id);
     }}
@@ -68,6 +109,17 @@ mod tests {
       d: ("BBFFBBFRLL", (102, 4), 820),
     }
 
+    parameterized_test::create!{bad_seats, input, {
+      assert!(BoardingPasses::parse(input).is_err());
+    }}
+    bad_seats!{
+      empty: "",
+      short: "FBFBBFFRL",
+      long: "FBFBBFFRLRR",
+      swapped: "FBFBBFRFLR",
+      unicode: "FBFBBFFRLé",
+    }
+
     #[test]
     fn strip_seq() {
         let set = vec!(2, 3, 4, 6, 9, 10, 12, 14, 15).into_iter().collect::<BTreeSet<_>>();
@@ -77,6 +129,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        assert!(parse_data().len() > 0);
+        assert!(parse_data(include_str!("../data/day05.txt")).len() > 0);
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<BoardingPasses>("generated boarding passes", 20, 2..=1000);
     }
 }
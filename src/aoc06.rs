@@ -1,15 +1,66 @@
 use std::collections::HashSet;
+use anyhow::{ensure, Result};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let groups = parse_data();
-    let qs: usize = groups.iter().map(|g| questions(g)).sum();
-    println!("All Answers: {}", qs);
-    let qs: usize = groups.iter().map(|g| all_questions(g)).sum();
-    println!("All Answers within each group: {}", qs);
+pub struct Groups(Vec<String>);
+
+impl Solution for Groups {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Groups(parse_data(input).into_iter().map(|g| g.to_string()).collect()))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let qs = self.0.iter().map(|g| questions(g)).sum::<usize>();
+        Console::detail(format!("All Answers: {}", qs));
+        Ok(qs.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let qs = self.0.iter().map(|g| all_questions(g)).sum::<usize>();
+        Console::detail(format!("All Answers within each group: {}", qs));
+        Ok(qs.into())
+    }
+
+    /// Generates `size` groups' answers.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_groups(rng, size)
+    }
+}
+
+// Everyone in a group answers its common questions, and each of its other questions is answered by
+// some but not all of the group
+fn generate_groups(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 groups");
+    let (mut part1, mut part2) = (0, 0);
+    let mut groups = Vec::new();
+    while groups.len() < size {
+        let people = rng.range(1, 6) as usize;
+        let mut letters: Vec<_> = ('a'..='z').collect();
+        rng.shuffle(&mut letters);
+        let common = rng.range(0, 6) as usize;
+        // A lone person answers everything they answer in common
+        let others = if people == 1 { 0 } else { rng.range(0, 10) as usize };
+        let mut answers = vec!(letters[..common].to_vec(); people);
+        for &letter in &letters[common..common + others] {
+            let answered = rng.range(1, people as u64) as usize;
+            for person in rng.distinct(answered, 0, people as u64) {
+                answers[person as usize].push(letter);
+            }
+        }
+        // Nobody can answer nothing, as that would be a blank line
+        if answers.iter().any(|a| a.is_empty()) { continue; }
+        part1 += common + others;
+        part2 += common;
+        groups.push(answers.into_iter().map(|mut a| { rng.shuffle(&mut a); a.into_iter().collect::<String>() })
+            .collect::<Vec<_>>().join("\n"));
+    }
+    Ok(Generated{ input: groups.join("\n\n") + "\n", part1: part1.into(), part2: part2.into() })
 }
 
-fn parse_data() -> Vec<&'static str> {
-    return include_str!("../data/day06.txt").trim().split("\n\n").collect();
+fn parse_data(input: &str) -> Vec<&str> {
+    return input.trim().split("\n\n").collect();
 }
 
 fn questions(group: &str) -> usize {
@@ -45,6 +96,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        assert!(parse_data().len() > 0);
+        assert!(parse_data(include_str!("../data/day06.txt")).len() > 0);
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Groups>("generated groups", 20, 1..=100);
     }
 }
@@ -1,12 +1,34 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use crate::parsing;
-use anyhow::{Context, Error, Result};
+use anyhow::{ensure, Context, Error, Result};
 use std::str::FromStr;
+use crate::generate::{Generated, Rng};
+use crate::console::Console;
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let bags = parse_data();
-    println!("Our bag can go in {} bag(s)", valid_containers(&bags, "shiny gold").len());
-    println!("Our bag can contain {} bag(s)", count_contents(&bags, "shiny gold"));
+pub struct BagRules(HashMap<String, Bag>);
+
+impl Solution for BagRules {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(BagRules(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let containers = valid_containers(&self.0, "shiny gold").len();
+        Console::detail(format!("Our bag can go in {} bag(s)", containers));
+        Ok(containers.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let contents = count_contents(&self.0, "shiny gold").map_err(Failure::no_solution)?;
+        Console::detail(format!("Our bag can contain {} bag(s)", contents));
+        Ok(contents.into())
+    }
+
+    /// Generates rules for `size` bags.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_rules(rng, size)
+    }
 }
 
 fn valid_containers(bags: &HashMap<String, Bag>, root: &str) -> HashSet<String> {
@@ -23,16 +45,17 @@ fn valid_containers(bags: &HashMap<String, Bag>, root: &str) -> HashSet<String>
     containers
 }
 
-fn count_contents(bags: &HashMap<String, Bag>, root: &str) -> u32 {
+fn count_contents(bags: &HashMap<String, Bag>, root: &str) -> Result<u32> {
     let mut count = 0;
     let mut frontier: VecDeque<_> = vec!((root.to_string(), 1)).into_iter().collect();
     while let Some((bag, bag_count)) = frontier.pop_front() {
-        for (contained, contained_count) in bags.get(&bag).expect("Unknown bag").contents.iter() {
+        let rule = bags.get(&bag).with_context(|| format!("No rule for {} bags", bag))?;
+        for (contained, contained_count) in rule.contents.iter() {
             frontier.push_back((contained.to_string(), bag_count * contained_count));
         }
         count += bag_count;
     }
-    count-1
+    Ok(count-1)
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -76,25 +99,75 @@ impl FromStr for Bag {
     }
 }
 
-fn parse_data() -> HashMap<String, Bag> {
-    Bag::build_map(&include_str!("../data/day07.txt")
-        .trim().split("\n").collect::<Vec<_>>()).unwrap()
+const ADJECTIVES: &[&str] = &[
+    "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "mirrored", "muted", "pale",
+    "plaid", "posh", "shiny", "striped", "vibrant", "wavy"];
+const COLORS: &[&str] = &[
+    "aqua", "beige", "black", "blue", "bronze", "brown", "chartreuse", "coral", "crimson", "cyan", "fuchsia",
+    "gold", "gray", "green", "indigo", "lavender", "lime", "magenta", "maroon", "olive", "orange", "plum", "purple",
+    "red", "salmon", "silver", "tan", "teal", "tomato", "turquoise", "violet", "white", "yellow"];
+
+// Bags only contain bags from deeper levels, which keeps the rules acyclic and the number of bags
+// inside shiny gold (which is always at level 2) manageable
+const LEVELS: u64 = 7;
+
+fn generate_rules(rng: &mut Rng, size: usize) -> Result<Generated> {
+    let names: Vec<_> = ADJECTIVES.iter().flat_map(|a| COLORS.iter().map(move |c| format!("{} {}", a, c)))
+        .filter(|n| n != "shiny gold").collect();
+    ensure!((1..=names.len() + 1).contains(&size), "Size should be between 1 and {} bags", names.len() + 1);
+    let mut bags: Vec<_> = rng.distinct(size - 1, 0, names.len() as u64).into_iter()
+        .map(|i| (names[i as usize].clone(), rng.range(0, LEVELS))).collect();
+    bags.push(("shiny gold".to_string(), 2));
+
+    let mut contents: HashMap<String, Vec<(u32, String)>> = HashMap::new();
+    for (name, level) in bags.iter() {
+        let deeper: Vec<_> = bags.iter().filter(|(_, l)| l > level).map(|(n, _)| n).collect();
+        // Shiny gold always holds something (if it can), so part 2 isn't trivial
+        let fewest = if name == "shiny gold" { 1 } else { 0 };
+        let count = rng.range(fewest, 4).min(deeper.len() as u64) as usize;
+        let mut inside: Vec<_> = rng.distinct(count, 0, deeper.len() as u64).into_iter()
+            .map(|i| deeper[i as usize].clone()).collect();
+        // Otherwise few bags would end up holding shiny gold
+        if *level < 2 && !inside.iter().any(|n| n == "shiny gold") && rng.chance(1, 3) {
+            inside.push("shiny gold".to_string());
+        }
+        contents.insert(name.clone(), inside.into_iter().map(|n| (rng.range(1, 6) as u32, n)).collect());
+    }
+
+    let mut lines: Vec<_> = bags.iter().map(|(name, _)| {
+        let inside = &contents[name];
+        let inside = if inside.is_empty() { "no other bags".to_string() } else {
+            inside.iter().map(|(n, bag)| format!("{} {} bag{}", n, bag, if *n == 1 { "" } else { "s" }))
+                .collect::<Vec<_>>().join(", ")
+        };
+        format!("{} bags contain {}.", name, inside)
+    }).collect();
+    rng.shuffle(&mut lines);
+
+    // Computed independently of valid_containers() and count_contents(), by memoized recursion
+    fn holds_gold(contents: &HashMap<String, Vec<(u32, String)>>, bag: &str, memo: &mut HashMap<String, bool>) -> bool {
+        if let Some(&holds) = memo.get(bag) { return holds; }
+        let holds = contents[bag].iter().any(|(_, b)| b == "shiny gold" || holds_gold(contents, b, memo));
+        memo.insert(bag.to_string(), holds);
+        holds
+    }
+    fn total_inside(contents: &HashMap<String, Vec<(u32, String)>>, bag: &str) -> i64 {
+        contents[bag].iter().map(|(n, b)| *n as i64 * (1 + total_inside(contents, b))).sum()
+    }
+    let mut memo = HashMap::new();
+    let holders = bags.iter().filter(|(n, _)| holds_gold(&contents, n, &mut memo)).count();
+    Ok(Generated{ input: lines.join("\n") + "\n", part1: holders.into(), part2: total_inside(&contents, "shiny gold").into() })
+}
+
+fn parse_data(input: &str) -> Result<HashMap<String, Bag>> {
+    Bag::build_map(&input
+        .trim().split("\n").collect::<Vec<_>>())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn parse_example1() -> HashMap<String, Bag> {
-        Bag::build_map(&include_str!("../data/day07_example1.txt")
-            .trim().split("\n").collect::<Vec<_>>()).unwrap()
-    }
-
-    fn parse_example2() -> HashMap<String, Bag> {
-        Bag::build_map(&include_str!("../data/day07_example2.txt")
-            .trim().split("\n").collect::<Vec<_>>()).unwrap()
-    }
-
     fn make_bag(name: &str, contents: Vec<(&str, u32)>) -> Bag {
         Bag { name: name.to_string(),
               contents: contents.iter().map(|(k,v)|(k.to_string(), *v)).collect() }
@@ -123,20 +196,15 @@ mod tests {
         i: ("dotted black bags contain no other bags.", make_bag("dotted black", vec!())),
     }
 
-    #[test]
-    fn containers() {
-        assert_eq!(valid_containers(&parse_example1(), "shiny gold").len(), 4);
-        assert_eq!(valid_containers(&parse_example2(), "shiny gold").len(), 0);
-    }
+    // The examples are checked by the data/day07_example*.answers fixtures
 
     #[test]
-    fn contains() {
-        assert_eq!(count_contents(&parse_example1(), "shiny gold"), 32);
-        assert_eq!(count_contents(&parse_example2(), "shiny gold"), 126);
+    fn parse_file() {
+        assert!(parse_data(include_str!("../data/day07.txt")).unwrap().len() > 0);
     }
 
     #[test]
-    fn parse_file() {
-        assert!(parse_data().len() > 0);
+    fn generated() {
+        crate::differential::check_generator::<BagRules>("generated bag rules", 20, 1..=100);
     }
 }
@@ -1,13 +1,81 @@
+use std::convert::TryFrom;
+use anyhow::{anyhow, ensure, Context, Result};
 use crate::machine::{Instruction, Program, Machine};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let program = parse_data().unwrap();
-    let mut machine = Machine::new();
-    assert!(!machine.run_until_complete(&program));
-    println!("Machine looped after setting accumulator to {}", machine.accumulator());
+pub struct BootCode(Program);
 
-    let (i, acc) = find_completable_program(&program);
-    println!("Machine completed after flipping command {} with accumulator set to {}", i, acc);
+impl Solution for BootCode {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(BootCode(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let mut machine = Machine::new();
+        if machine.run_until_complete(&self.0).map_err(Failure::no_solution)? {
+            return Err(Failure::no_solution(anyhow!("Program unexpectedly completed")));
+        }
+        Console::detail(format!("Machine looped after setting accumulator to {}", machine.accumulator()));
+        Ok(machine.accumulator().into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let (i, acc) = find_completable_program(&self.0).context("No valid programs").map_err(Failure::no_solution)?;
+        Console::detail(format!("Machine completed after flipping command {} with accumulator set to {}", i, acc));
+        Ok(acc.into())
+    }
+
+    /// Generates a program of `size` instructions.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_program(rng, size)
+    }
+}
+
+// Random programs are generated until one loops, and flipping exactly one of its instructions
+// makes it complete. The programs are run by a separate interpreter from Machine's.
+fn generate_program(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((2..=300).contains(&size), "Size should be between 2 and 300 instructions");
+    // Whether the program completes, and the accumulator once it completes or loops; None if it
+    // jumps out of bounds
+    fn run(program: &[(&str, i64)]) -> Option<(bool, i64)> {
+        let (mut counter, mut acc) = (0, 0);
+        let mut seen = vec!(false; program.len());
+        while counter != program.len() as i64 {
+            let index = usize::try_from(counter).ok().filter(|&c| c < program.len())?;
+            if seen[index] { return Some((false, acc)); }
+            seen[index] = true;
+            match program[index] {
+                ("acc", arg) => { acc += arg; counter += 1; },
+                ("jmp", arg) => counter += arg,
+                _ => counter += 1,
+            }
+        }
+        Some((true, acc))
+    }
+
+    loop {
+        let mut program: Vec<_> = (0..size as i64).map(|i| match rng.range(0, 10) {
+            0..=4 => ("acc", rng.range(0, 100) as i64 - 50),
+            5..=7 => ("jmp", rng.range(0, size as u64 + 1) as i64 - i),
+            _ => ("nop", rng.range(0, size as u64 + 1) as i64 - i),
+        }).collect();
+        let part1 = match run(&program) {
+            Some((false, acc)) => acc,
+            _ => continue,
+        };
+        let mut fixes = Vec::new();
+        for i in 0..size {
+            let original = program[i];
+            program[i].0 = match original.0 { "jmp" => "nop", "nop" => "jmp", _ => continue };
+            if let Some((true, acc)) = run(&program) { fixes.push(acc); }
+            program[i] = original;
+        }
+        if fixes.len() != 1 { continue; }
+        let input = program.iter().map(|(op, arg)| format!("{} {:+}", op, arg)).collect::<Vec<_>>().join("\n");
+        return Ok(Generated{ input: input + "\n", part1: part1.into(), part2: fixes[0].into() });
+    }
 }
 
 fn swap_jmp_nop(instr: &Instruction) -> Instruction {
@@ -18,20 +86,22 @@ fn swap_jmp_nop(instr: &Instruction) -> Instruction {
     }
 }
 
-fn find_completable_program(program: &Program) -> (usize, i32) {
+fn find_completable_program(program: &Program) -> Option<(usize, i32)> {
     for i in 0..program.commands.len() {
         let mut machine = Machine::new();
         let mut program = program.clone();
         program.commands[i] = swap_jmp_nop(&program.commands[i]);
-        if machine.run_until_complete(&program) {
-            return (i, machine.accumulator());
+        // Swapping an instruction can send the program out of bounds, which is just another way
+        // for it to not complete
+        if let Ok(true) = machine.run_until_complete(&program) {
+            return Some((i, machine.accumulator()));
         }
     }
-    panic!("No valid programs.");
+    None
 }
 
-fn parse_data() -> anyhow::Result<Program> {
-    include_str!("../data/day08.txt").parse()
+fn parse_data(input: &str) -> Result<Program> {
+    input.parse()
 }
 
 #[cfg(test)]
@@ -46,20 +116,25 @@ mod tests {
     fn find_loop() {
         let program = parse_example().unwrap();
         let mut machine = Machine::new();
-        assert!(!machine.run_until_complete(&program));
+        assert!(!machine.run_until_complete(&program).unwrap());
         assert_eq!(machine.accumulator(), 5);
     }
 
     #[test]
     fn fix_program() {
         let program = parse_example().unwrap();
-        let (i, acc) = find_completable_program(&program);
+        let (i, acc) = find_completable_program(&program).unwrap();
         assert_eq!(i, 7);
         assert_eq!(acc, 8);
     }
 
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day08.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<BootCode>("generated programs", 20, 2..=300);
     }
 }
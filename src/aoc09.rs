@@ -1,18 +1,104 @@
 use std::num::ParseIntError;
+use anyhow::{ensure, Context, Result};
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let data = parse_data().unwrap();
-    let invalid = find_non_sum(&data, 25).unwrap();
-    println!("First invalid number: {}", invalid);
-    let sequence = find_contiguous_sum(invalid, &data).unwrap();
-    // TODO https://doc.rust-lang.org/1.1.0/std/iter/trait.Iterator.html#method.min_max
-    println!("MinMax of contiguous sequence: {}",
-             sequence.iter().min().unwrap() + sequence.iter().max().unwrap());
+pub struct Xmas {
+    stream: Vec<i64>,
+    window: usize,
 }
 
+impl Xmas {
+    fn invalid(&self) -> Result<i64> {
+        find_non_sum(&self.stream, self.window).context("No invalid number").map_err(Failure::no_solution)
+    }
+}
+
+impl Solution for Xmas {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Xmas{ stream: parse_data(input)?, window: 25 })
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "window", kind: Kind::Number{ min: 2 }, default: Some("25"),
+               help: "How many preceding numbers each number must be a sum of two of" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(window) = options.number("window") {
+            self.window = window;
+        }
+        Ok(())
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let invalid = self.invalid()?;
+        Console::detail(format!("First invalid number: {}", invalid));
+        Ok(invalid.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let sequence = find_contiguous_sum(self.invalid()?, &self.stream).context("No contiguous sum").map_err(Failure::no_solution)?;
+        // TODO https://doc.rust-lang.org/1.1.0/std/iter/trait.Iterator.html#method.min_max
+        let min_max = sequence.iter().min().unwrap() + sequence.iter().max().unwrap();
+        Console::detail(format!("MinMax of contiguous sequence: {}", min_max));
+        Ok(min_max.into())
+    }
+
+    /// Generates `size` numbers after a preamble of 25, the default window.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_stream(rng, size)
+    }
+}
+
+// Every number after the preamble is the sum of two earlier numbers in its window, except one, which
+// is the sum of a planted run of earlier numbers instead. Streams are redrawn until the planted run
+// is the only run (of at least two numbers) that sums to the invalid number.
+fn generate_stream(rng: &mut Rng, size: usize) -> Result<Generated> {
+    const WINDOW: usize = 25;
+    ensure!((1..=500).contains(&size), "Size should be between 1 and 500 numbers");
+    let (stream, invalid, part2) = loop {
+        let invalid_at = WINDOW + rng.range(0, size as u64) as usize;
+        let mut stream: Vec<i64> = rng.distinct(WINDOW, 1, 100).into_iter().map(|n| n as i64).collect();
+        while stream.len() < invalid_at {
+            stream.push(window_sum(rng, &stream[stream.len()-WINDOW..]));
+        }
+        let (invalid, run) = loop {
+            let start = rng.range(0, invalid_at as u64 - 1) as usize;
+            let end = rng.range(start as u64 + 2, (invalid_at.min(start + 10) + 1) as u64) as usize;
+            let invalid: i64 = stream[start..end].iter().sum();
+            if !found_sum_in_window(invalid, &stream[invalid_at-WINDOW..]) { break (invalid, start..end); }
+        };
+        let part2 = stream[run.clone()].iter().min().unwrap() + stream[run].iter().max().unwrap();
+        stream.push(invalid);
+        while stream.len() < WINDOW + size {
+            stream.push(window_sum(rng, &stream[stream.len()-WINDOW..]));
+        }
+        if count_contiguous_sums(invalid, &stream) == 1 { break (stream, invalid, part2); }
+    };
+    let input = stream.iter().map(|n| n.to_string()).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: invalid.into(), part2: part2.into() })
+}
+
+// All numbers are positive, so each run can stop growing once it reaches the value
+fn count_contiguous_sums(value: i64, stream: &[i64]) -> usize {
+    (0..stream.len()).filter(|&start| {
+        let mut sum = stream[start];
+        stream[start+1..].iter().any(|n| { sum += n; sum >= value }) && sum == value
+    }).count()
+}
+
+fn window_sum(rng: &mut Rng, window: &[i64]) -> i64 {
+    let picked = rng.distinct(2, 0, window.len() as u64);
+    window[picked[0] as usize] + window[picked[1] as usize]
+}
+
+// Each number after the first window_size must be a sum of two of the window_size before it
 fn find_non_sum(stream: &[i64], window_size: usize) -> Option<i64> {
-    for i in window_size+1..stream.len() {
-        if !found_sum_in_window(stream[i], &stream[i-1-window_size..i]) {
+    for i in window_size..stream.len() {
+        if !found_sum_in_window(stream[i], &stream[i-window_size..i]) {
             return Some(stream[i]);
         }
     }
@@ -28,9 +114,10 @@ fn found_sum_in_window(value: i64, window: &[i64]) -> bool {
     return false;
 }
 
+// The range must span at least two numbers, so it can't just be the value itself
 fn find_contiguous_sum<'a>(value: i64, stream: &'a[i64]) -> Option<&'a[i64]> {
-    for start in 0..stream.len()-1 {
-        for end in start+1..stream.len() {
+    for start in 0..stream.len() {
+        for end in start+2..=stream.len() {
             let sum: i64 = stream[start..end].iter().sum();
             if sum == value { return Some(&stream[start..end]); }
             if sum > value { break; }
@@ -40,8 +127,8 @@ fn find_contiguous_sum<'a>(value: i64, stream: &'a[i64]) -> Option<&'a[i64]> {
     None
 }
 
-fn parse_data() -> Result<Vec<i64>, ParseIntError> {
-    include_str!("../data/day09.txt").trim()
+fn parse_data(input: &str) -> std::result::Result<Vec<i64>, ParseIntError> {
+    input.trim()
         .split("\n").map(|n| n.parse()).collect()
 }
 
@@ -57,13 +144,31 @@ mod tests {
         assert_eq!(find_non_sum(&EXAMPLE, 5), Some(127));
     }
 
+    parameterized_test::create!{window_bounds, (stream, expected), {
+        assert_eq!(find_non_sum(stream, 3), expected);
+    }}
+    window_bounds!{
+        // 10 follows the first window immediately
+        first: (&[1, 2, 3, 10], Some(10)),
+        // 2+4 is only a sum if the window includes one number too many
+        too_wide: (&[1, 2, 3, 4, 5, 6], Some(6)),
+        valid: (&[1, 2, 3, 5, 8], None),
+    }
+
     #[test]
     fn find_sum() {
         assert_eq!(find_contiguous_sum(127, &EXAMPLE), Some(&[15, 25, 47, 40][..]));
+        assert_eq!(find_contiguous_sum(20, EXAMPLE), None);
+        assert_eq!(find_contiguous_sum(5, &[1, 4]), Some(&[1, 4][..]));
     }
 
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day09.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Xmas>("generated streams", 20, 1..=200);
     }
 }
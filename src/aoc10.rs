@@ -1,11 +1,52 @@
 use std::num::ParseIntError;
 use std::collections::HashMap;
+use anyhow::{bail, ensure, Result};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let adapters = parse_data().unwrap();
-    let counts = adapter_deltas(&adapters);
-    println!("Adapter delta histogram: {:?} - delta-1*3: {}", counts, counts[0] * (counts[2]));
-    println!("Possible valid combinations: {}", adapter_combos(&adapters));
+pub struct Adapters(Vec<i64>);
+
+impl Solution for Adapters {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Adapters(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let counts = adapter_deltas(&self.0);
+        Console::detail(format!("Adapter delta histogram: {:?} - delta-1*3: {}", counts, counts[0] * counts[2]));
+        Ok((counts[0] * counts[2]).into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let combos = adapter_combos(&self.0);
+        Console::detail(format!("Possible valid combinations: {}", combos));
+        Ok(combos.into())
+    }
+
+    /// Generates `size` adapters.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_adapters(rng, size)
+    }
+}
+
+// Like puzzle inputs, adapters differ by 1 or 3 jolts with at most four 1-jolt steps in a row, which
+// lets linear_adapter_combos() count the combinations independently of adapter_combos().
+fn generate_adapters(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=100).contains(&size), "Size should be between 1 and 100 adapters");
+    let mut deltas = Vec::with_capacity(size);
+    while deltas.len() < size {
+        let run = deltas.iter().rev().take_while(|&&d| d == 1).count();
+        deltas.push(if run < 4 && rng.chance(2, 3) { 1 } else { 3 });
+    }
+    let mut adapters: Vec<i64> = deltas.iter().scan(0, |jolts, d| { *jolts += d; Some(*jolts) }).collect();
+    let ones = deltas.iter().filter(|&&d| d == 1).count();
+    // The device is always 3 jolts above the last adapter
+    let part1 = ones * (size - ones + 1);
+    let part2 = linear_adapter_combos(&prepare_data(adapters.clone()));
+    rng.shuffle(&mut adapters);
+    let input = adapters.iter().map(|a| a.to_string()).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: part2.into() })
 }
 
 fn adapter_deltas(adapters: &[i64]) -> [i64; 3] {
@@ -13,7 +54,7 @@ fn adapter_deltas(adapters: &[i64]) -> [i64; 3] {
     let mut input_jolts = 0;
     for a in adapters[1..].iter() {
         let delta = a - input_jolts;
-        assert!(delta >= 1 && delta <= 3, "Unexpected delta {}-{} = {}", a, input_jolts, delta);
+        assert!(delta >= 1 && delta <= 3, "Unexpected delta {}-{} = {}", a, input_jolts, delta); // see parse_data()
         counts[delta as usize -1] += 1;
         input_jolts = *a;
     }
@@ -21,7 +62,6 @@ fn adapter_deltas(adapters: &[i64]) -> [i64; 3] {
 }
 
 // From https://old.reddit.com/r/adventofcode/comments/ka9pc3/2020_day_10_part_2_suspicious_factorisation/gf94sxy/
-#[cfg(test)]
 fn linear_adapter_combos(adapters: &[i64]) -> i64 {
     let (mut pow2, mut pow7) = (0, 0);
     for i in 1..adapters.len()-1 {
@@ -58,20 +98,24 @@ fn adapter_combos_cached(adapters: &[i64], i: usize, cache: &mut HashMap<usize,
 fn prepare_data(mut data: Vec<i64>) -> Vec<i64> {
     data.push(0);
     data.sort();
-    assert_eq!(data[0], 0);
     data.push(data[data.len()-1]+3);
     data
 }
 
-fn parse_data() -> Result<Vec<i64>, ParseIntError> {
-    include_str!("../data/day10.txt").trim().split("\n")
-        .map(|n| n.parse()).collect::<Result<Vec<_>, _>>()
-        .map(|v| prepare_data(v))
+fn parse_data(input: &str) -> Result<Vec<i64>> {
+    let adapters = prepare_data(input.trim().split("\n")
+        .map(|n| n.parse()).collect::<std::result::Result<Vec<_>, ParseIntError>>()?);
+    ensure!(adapters[0] == 0 && adapters[1] > 0, "Adapters must be positive");
+    if let Some(gap) = adapters.windows(2).find(|w| w[1] - w[0] < 1 || w[1] - w[0] > 3) {
+        bail!("Adapters must differ by 1-3 jolts: {} and {}", gap[0], gap[1]);
+    }
+    Ok(adapters)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{self, Differential, Rng};
 
     lazy_static!{
         static ref EXAMPLE_A: Vec<i64> = prepare_data(vec!(16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4));
@@ -94,13 +138,61 @@ mod tests {
         assert_eq!(linear_adapter_combos(&EXAMPLE_B), 19208);
     }
 
+    // linear_adapter_combos() relies on the structure of puzzle inputs: adapters differ by 1 or 3
+    // jolts, never 2, with no more than four 1-jolt steps in a row.
+    fn chain(deltas: &[i64]) -> Option<Vec<i64>> {
+        let longest_run = deltas.split(|&d| d == 3).map(|run| run.len()).max().unwrap_or(0);
+        if deltas.is_empty() || deltas.iter().any(|&d| d != 1 && d != 3) || longest_run > 4 {
+            return None;
+        }
+        let adapters = deltas.iter().scan(0, |jolts, d| { *jolts += d; Some(*jolts) }).collect();
+        Some(prepare_data(adapters))
+    }
+
+    fn deltas(adapters: &[i64]) -> Vec<i64> {
+        adapters.windows(2).map(|w| w[1] - w[0]).take(adapters.len() - 2).collect()
+    }
+
+    fn generate_chain(rng: &mut Rng) -> Vec<i64> {
+        loop {
+            let deltas: Vec<_> = (0..rng.range(1, 60)).map(|_| if rng.chance(2, 3) { 1 } else { 3 }).collect();
+            if let Some(adapters) = chain(&deltas) {
+                return adapters;
+            }
+        }
+    }
+
+    #[test]
+    fn differential() {
+        Differential::new("adapter combos")
+            .implementation("cached", |adapters: &Vec<i64>| adapter_combos(adapters))
+            .implementation("linear", |adapters: &Vec<i64>| linear_adapter_combos(adapters))
+            .check(200, 10, generate_chain,
+                   |adapters| differential::remove_one(&deltas(adapters), 1).iter().filter_map(|d| chain(d)).collect());
+    }
+
     #[test]
     fn prepare() {
         let nums = vec!(4, 2, 10, 7, 3);
         assert_eq!(prepare_data(nums), vec!(0, 2, 3, 4, 7, 10, 13));
     }
+    parameterized_test::create!{invalid, input, {
+        assert!(parse_data(input).is_err());
+    }}
+    invalid!{
+        gap: "1\n2\n6",
+        duplicate: "1\n2\n2",
+        negative: "-1\n1",
+        not_a_number: "1\nx",
+    }
+
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day10.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        differential::check_generator::<Adapters>("generated adapters", 20, 1..=100);
     }
 }
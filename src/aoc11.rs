@@ -2,16 +2,35 @@ use std::collections::HashMap;
 use crate::euclid::{Point,point,vector,Vector};
 use crate::console::{Color, Console};
 use std::fmt;
+use anyhow::{bail, ensure, Context, Result};
+use crate::budget;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
+
+impl Solution for Floor {
+    fn parse(input: &str) -> Result<Self> {
+        Console::colorize_char('L', Color::BLUE);
+        Console::colorize_char('#', Color::YELLOW);
+        Console::colorize_char('.', Color::GREY);
+        build_map(input)
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let occupied = count_occupied(&find_stable(self, &Adjacent{})?);
+        Console::detail(format!("Occupied seats with adjacency: {}", occupied));
+        Ok(occupied.into())
+    }
 
-pub fn advent() {
-    Console::colorize_char('L', Color::BLUE);
-    Console::colorize_char('#', Color::YELLOW);
-    Console::colorize_char('.', Color::GREY);
-    let floorplan = parse_data();
-    println!("Occupied seats with adjacency: {}",
-             count_occupied(&find_stable(&floorplan, &Adjacent{})));
-    println!("Occupied seats with visibility: {}",
-             count_occupied(&find_stable(&floorplan, &Visible{})));
+    fn part2(&self) -> Result<Answer> {
+        let occupied = count_occupied(&find_stable(self, &Visible{})?);
+        Console::detail(format!("Occupied seats with visibility: {}", occupied));
+        Ok(occupied.into())
+    }
+
+    /// Generates a `size` by `size` seating area.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_seats(rng, size)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -22,7 +41,7 @@ enum State {
 }
 
 #[derive(Eq, PartialEq, Clone)]
-struct Floor {
+pub struct Floor {
     points: HashMap<Point, State>,
 }
 
@@ -108,16 +127,17 @@ fn count_occupied(floor: &Floor) -> usize {
     floor.points.values().filter(|s| **s==State::Occupied).count()
 }
 
-fn find_stable(floor: &Floor, strat: &dyn Strategy) -> Floor {
+fn find_stable(floor: &Floor, strat: &dyn Strategy) -> Result<Floor> {
     let mut last = floor.clone();
-    loop {
+    for rounds in 0.. {
+        budget::check().with_context(|| format!("Ran {} rounds without stabilizing", rounds))?;
+        Console::interactive_display(&last, std::time::Duration::from_millis(50));
         let next = iteration(&last, strat);
         if next == last { break; }
-        Console::interactive_display(last, std::time::Duration::from_millis(50));
         last = next;
     }
     Console::clear_interactive();
-    last
+    Ok(last)
 }
 
 fn iteration(floor: &Floor, strat: &dyn Strategy) -> Floor {
@@ -131,7 +151,50 @@ fn iteration(floor: &Floor, strat: &dyn Strategy) -> Floor {
     Floor { points: next }
 }
 
-fn build_map(str: &str) -> Floor {
+// Lays out empty seats and floor at random, and finds the answers with a plain grid simulation
+// rather than the Strategy implementations.
+fn generate_seats(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=100).contains(&size), "Size should be between 1 and 100 rows");
+    let grid: Vec<Vec<char>> = (0..size)
+        .map(|_| (0..size).map(|_| if rng.chance(3, 4) { 'L' } else { '.' }).collect())
+        .collect();
+    let input = grid.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: simulate_grid(&grid, 4, false).into(), part2: simulate_grid(&grid, 5, true).into() })
+}
+
+fn simulate_grid(grid: &[Vec<char>], crowded: usize, visible: bool) -> usize {
+    let (rows, cols) = (grid.len() as i32, grid[0].len() as i32);
+    let mut grid = grid.to_vec();
+    loop {
+        let next: Vec<Vec<char>> = (0..rows).map(|y| (0..cols).map(|x| {
+            let seat = grid[y as usize][x as usize];
+            if seat == '.' { return seat; }
+            let mut count = 0;
+            for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy))).filter(|&d| d != (0, 0)) {
+                let (mut nx, mut ny) = (x + dx, y + dy);
+                while nx >= 0 && nx < cols && ny >= 0 && ny < rows {
+                    match grid[ny as usize][nx as usize] {
+                        '#' => { count += 1; break; },
+                        'L' => break,
+                        _ => if !visible { break; },
+                    }
+                    nx += dx;
+                    ny += dy;
+                }
+            }
+            match seat {
+                'L' if count == 0 => '#',
+                '#' if count >= crowded => 'L',
+                _ => seat,
+            }
+        }).collect()).collect();
+        if next == grid { break; }
+        grid = next;
+    }
+    grid.iter().flatten().filter(|&&c| c == '#').count()
+}
+
+fn build_map(str: &str) -> Result<Floor> {
     let rows: Vec<_> = str.trim().split("\n").collect();
     let mut points = HashMap::new();
     let mut pos = point(0, 0);
@@ -141,18 +204,15 @@ fn build_map(str: &str) -> Floor {
                 'L' => State::Empty,
                 '#' => State::Occupied,
                 '.' => State::Floor,
-                _ => panic!(),
+                _ => bail!("Unexpected character {:?} at {}", col, pos),
             };
             points.insert(pos, state);
             pos += vector(1, 0);
         }
         pos = point(0, pos.y+1);
     }
-    Floor { points }
-}
-
-fn parse_data() -> Floor {
-    build_map(include_str!("../data/day11.txt"))
+    ensure!(!points.is_empty(), "No seats");
+    Ok(Floor { points })
 }
 
 #[cfg(test)]
@@ -160,21 +220,32 @@ mod tests {
     use super::*;
 
     fn parse_example() -> Floor {
-        build_map(include_str!("../data/day11_example.txt"))
+        build_map(include_str!("../data/day11_example.txt")).unwrap()
     }
 
     #[test]
     fn adjacent() {
-        assert_eq!(count_occupied(&find_stable(&parse_example(), &Adjacent{})), 37);
+        assert_eq!(count_occupied(&find_stable(&parse_example(), &Adjacent{}).unwrap()), 37);
     }
 
     #[test]
     fn visible() {
-        assert_eq!(count_occupied(&find_stable(&parse_example(), &Visible{})), 26);
+        assert_eq!(count_occupied(&find_stable(&parse_example(), &Visible{}).unwrap()), 26);
+    }
+
+    #[test]
+    fn bad_input() {
+        assert!(build_map("L.L\n.x.").is_err());
+        assert!(build_map("\n").is_err());
     }
 
     #[test]
     fn parse_file() {
-        parse_data();
+        Floor::parse(include_str!("../data/day11.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Floor>("generated seats", 10, 1..=30);
     }
 }
@@ -1,12 +1,34 @@
 use crate::euclid::{point, vector, Vector, Point};
 use std::str::FromStr;
-use anyhow::{Error, bail, Context, Result};
+use anyhow::{Error, bail, ensure, Context, Result};
 use std::fmt;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let path = parse_data();
-    println!("Direct destination: {}", (move_direct(&path)-Point::ORIGIN).grid_len());
-    println!("Waypoint destination: {}", (move_relative(&path)-Point::ORIGIN).grid_len());
+pub struct Navigation(Vec<Move>);
+
+impl Solution for Navigation {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Navigation(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let distance = (move_direct(&self.0)-Point::ORIGIN).grid_len();
+        Console::detail(format!("Direct destination: {}", distance));
+        Ok(distance.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let distance = (move_relative(&self.0)-Point::ORIGIN).grid_len();
+        Console::detail(format!("Waypoint destination: {}", distance));
+        Ok(distance.into())
+    }
+
+    /// Generates `size` moves.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_moves(rng, size)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -47,6 +69,9 @@ impl FromStr for Move {
             'F' => Instruction::Forward,
             _ => bail!("Invalid letter {}", letter),
         };
+        if let Instruction::Left | Instruction::Right = instruction {
+            ensure!(length % 90 == 0, "Can only turn in multiples of 90 degrees: {}", entry);
+        }
         Ok(Move{instruction, length})
     }
 }
@@ -102,8 +127,41 @@ fn move_relative(path: &[Move]) -> Point {
     pos
 }
 
-fn parse_data() -> Vec<Move> {
-    include_str!("../data/day12.txt").trim().split("\n").map(|m| m.parse().unwrap()).collect()
+// Picks moves at random and follows them with plain (east, north) pairs, turning a quarter at a time
+// rather than using rotate(). Lengths are kept small enough that positions fit in an i32.
+fn generate_moves(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 moves");
+    let (mut ship, mut heading) = ((0_i64, 0_i64), (1_i64, 0_i64));
+    let (mut relative, mut waypoint) = ((0_i64, 0_i64), (10_i64, 1_i64));
+    let mut moves = Vec::with_capacity(size);
+    for _ in 0..size {
+        let letter = ['N', 'S', 'E', 'W', 'L', 'R', 'F', 'F'][rng.range(0, 8) as usize];
+        let length = match letter {
+            'L' | 'R' => rng.range(1, 4) as i64 * 90,
+            'F' => rng.range(1, 101) as i64,
+            _ => rng.range(1, 6) as i64,
+        };
+        moves.push(format!("{}{}", letter, length));
+        let step = match letter { 'N' => (0, 1), 'S' => (0, -1), 'E' => (1, 0), 'W' => (-1, 0), _ => (0, 0) };
+        ship = (ship.0 + step.0 * length, ship.1 + step.1 * length);
+        waypoint = (waypoint.0 + step.0 * length, waypoint.1 + step.1 * length);
+        let quarters = match letter { 'R' => length / 90, 'L' => 4 - length / 90, _ => 0 };
+        for _ in 0..quarters {
+            heading = (heading.1, -heading.0);
+            waypoint = (waypoint.1, -waypoint.0);
+        }
+        if letter == 'F' {
+            ship = (ship.0 + heading.0 * length, ship.1 + heading.1 * length);
+            relative = (relative.0 + waypoint.0 * length, relative.1 + waypoint.1 * length);
+        }
+    }
+    let part1 = ship.0.abs() + ship.1.abs();
+    let part2 = relative.0.abs() + relative.1.abs();
+    Ok(Generated{ input: moves.join("\n") + "\n", part1: part1.into(), part2: part2.into() })
+}
+
+fn parse_data(input: &str) -> Result<Vec<Move>> {
+    input.trim().split("\n").map(|m| m.parse()).collect()
 }
 
 #[cfg(test)]
@@ -126,6 +184,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        parse_data();
+        parse_data(include_str!("../data/day12.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Navigation>("generated moves", 20, 1..=200);
     }
 }
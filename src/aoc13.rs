@@ -1,31 +1,91 @@
-pub fn advent() {
-    // Observation: all non-x inputs are primes.
-    // https://www.wolframalpha.com/input/?i=lcm+of+37%2C+41%2C+601%2C+19%2C+17%2C+23%2C+29%2C+443%2C+13
+use anyhow::{ensure, Context, Result};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
+
+pub struct Schedule {
+    timestamp: i64,
+    routes: Vec<Option<i64>>,
+}
+
+impl Solution for Schedule {
+    fn parse(input: &str) -> Result<Self> {
+        let (timestamp, routes) = parse_data(input)?;
+        Ok(Schedule{timestamp, routes})
+    }
 
-    let (timestamp, routes) = parse_data();
-    let (route, wait_time) = next_bus(timestamp, &routes.iter().filter_map(|&e| e).collect::<Vec<_>>());
-    println!("Route {} will arrive in {} minutes, value: {}", route, wait_time, route * wait_time);
-    println!("Earliest sequential timestamp: {}", find_timestamp(&routes));
+    fn part1(&self) -> Result<Answer> {
+        // Observation: all non-x inputs are primes.
+        // https://www.wolframalpha.com/input/?i=lcm+of+37%2C+41%2C+601%2C+19%2C+17%2C+23%2C+29%2C+443%2C+13
+        let (route, wait_time) = next_bus(self.timestamp, &self.routes.iter().filter_map(|&e| e).collect::<Vec<_>>());
+        Console::detail(format!("Route {} will arrive in {} minutes, value: {}", route, wait_time, route * wait_time));
+        Ok((route * wait_time).into())
+    }
 
-    // Intuitive writeup at https://old.reddit.com/r/adventofcode/comments/kcb3bb/2020_day_13_part_2_can_anyone_tell_my_why_this/
+    fn part2(&self) -> Result<Answer> {
+        // Intuitive writeup at https://old.reddit.com/r/adventofcode/comments/kcb3bb/2020_day_13_part_2_can_anyone_tell_my_why_this/
 
-    // But, lo, WolframAlpha can just solve Part 2 directly...
-    // https://www.wolframalpha.com/input/?i=7a%3Dt%2C+13b-1%3Dt%2C+59c-4%3Dt%2C+31d-6%3Dt%2C+19f-7%3Dt
-    // https://www.wolframalpha.com/input/?i=37a-0%3Dt%2C+41b-27%3Dt%2C+601c-37%3Dt%2C+19d-49%3Dt%2C+17f-54%3Dt%2C+23g-60%3Dt%2C+29h-66%3Dt%2C+443i-68%3Dt%2C+13j-81%3Dt
+        // But, lo, WolframAlpha can just solve Part 2 directly...
+        // https://www.wolframalpha.com/input/?i=7a%3Dt%2C+13b-1%3Dt%2C+59c-4%3Dt%2C+31d-6%3Dt%2C+19f-7%3Dt
+        // https://www.wolframalpha.com/input/?i=37a-0%3Dt%2C+41b-27%3Dt%2C+601c-37%3Dt%2C+19d-49%3Dt%2C+17f-54%3Dt%2C+23g-60%3Dt%2C+29h-66%3Dt%2C+443i-68%3Dt%2C+13j-81%3Dt
+        let timestamp = find_timestamp(&self.routes).context("Routes must be pairwise coprime").map_err(Failure::no_solution)?;
+        Console::detail(format!("Earliest sequential timestamp: {}", timestamp));
+        Ok(timestamp.into())
+    }
+
+    /// Generates a schedule of `size` entries.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_schedule(rng, size)
+    }
 }
 
 fn next_bus(timestamp: i64, routes: &[i64]) -> (i64, i64) {
     routes.iter().map(|&r| (r, r - (timestamp%r))).min_by_key(|&(_,d)|d).unwrap()
 }
 
-fn find_timestamp(routes: &[Option<i64>]) -> i64 {
+fn find_timestamp(routes: &[Option<i64>]) -> Option<i64> {
     let constraints: Vec<_> = routes.iter().enumerate().filter_map(|(i, r)| r.map(|r| (i as i64, r))).map(|(i, r)| ((((r-i) % r)+r)%r, r)).collect();
-    rosetta::chinese_remainder(&constraints).unwrap()
+    rosetta::chinese_remainder(&constraints)
 }
 
-fn parse_data() -> (i64, Vec<Option<i64>>) {
-    let lines: Vec<_> = include_str!("../data/day13.txt").split("\n").collect();
-    (lines[0].parse().unwrap(), lines[1].split(",").map(|e|e.parse().ok()).collect())
+// Like real schedules, routes are distinct primes and no bus leaves exactly at the timestamp. Part 2
+// is found by sieving, one route at a time, rather than with the chinese remainder theorem. At most
+// eight routes below 100 keep the chinese remainder sums well within an i64.
+fn generate_schedule(rng: &mut Rng, size: usize) -> Result<Generated> {
+    const PRIMES: [i64; 25] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97];
+    ensure!((1..=100).contains(&size), "Size should be between 1 and 100 entries");
+    let count = rng.range(1, size.min(8) as u64 + 1) as usize;
+    let mut routes = vec!(None; size);
+    let primes = rng.distinct(count, 0, PRIMES.len() as u64);
+    for (position, prime) in rng.distinct(count, 0, size as u64).into_iter().zip(primes) {
+        routes[position as usize] = Some(PRIMES[prime as usize]);
+    }
+    let buses: Vec<i64> = routes.iter().flatten().cloned().collect();
+    // Redraw until one bus is strictly first, and none leaves right away
+    let (timestamp, part1) = loop {
+        let timestamp = rng.range(1, 1_000_000) as i64;
+        let mut waits: Vec<_> = buses.iter().map(|&b| ((b - timestamp % b) % b, b)).collect();
+        waits.sort();
+        if waits[0].0 > 0 && (waits.len() == 1 || waits[0].0 < waits[1].0) {
+            break (timestamp, waits[0].0 * waits[0].1);
+        }
+    };
+    let (mut part2, mut step) = (0, 1);
+    for (offset, route) in routes.iter().enumerate().filter_map(|(i, r)| r.map(|r| (i as i64, r))) {
+        while (part2 + offset) % route != 0 { part2 += step; }
+        step *= route;
+    }
+    let schedule = routes.iter().map(|r| r.map(|r| r.to_string()).unwrap_or_else(|| "x".into())).collect::<Vec<_>>();
+    Ok(Generated{ input: format!("{}\n{}\n", timestamp, schedule.join(",")), part1: part1.into(), part2: part2.into() })
+}
+
+fn parse_data(input: &str) -> Result<(i64, Vec<Option<i64>>)> {
+    let lines: Vec<_> = input.split("\n").collect();
+    anyhow::ensure!(lines.len() >= 2, "Expected a timestamp and a list of routes");
+    let routes: Vec<Option<i64>> = lines[1].split(",").map(|e|e.parse().ok()).collect();
+    anyhow::ensure!(routes.iter().any(|r| r.is_some()), "No routes: {}", lines[1]);
+    anyhow::ensure!(routes.iter().flatten().all(|&r| r > 0), "Routes must be positive: {}", lines[1]);
+    Ok((lines[0].parse().with_context(|| format!("Invalid timestamp: {}", lines[0]))?, routes))
 }
 
 // https://rosettacode.org/wiki/Chinese_remainder_theorem#Rust
@@ -82,6 +142,8 @@ mod rosetta {
     #[cfg(test)]
     mod tests {
         use super::*;
+        use crate::differential::{self, Differential, Rng};
+
         #[test]
         fn validate() {
             let residues = [2,3,2];
@@ -91,6 +153,43 @@ mod rosetta {
             let zipped: Vec<_> = residues.iter().zip(&modulii).map(|(&r,&m)|(r,m)).collect();
             assert_eq!(chinese_remainder(&zipped), Some(23));
         }
+
+        // The smallest non-negative solution, by checking every candidate
+        fn brute_force(elements: &[(i64,i64)]) -> Option<i64> {
+            let prod = elements.iter().map(|&(_, m)| m).product::<i64>();
+            (0..prod).find(|t| elements.iter().all(|&(r, m)| t % m == r))
+        }
+
+        // Like bus routes, the modulii are distinct primes; small enough that nothing overflows
+        fn generate_constraints(rng: &mut Rng) -> Vec<(i64,i64)> {
+            const PRIMES: [u64; 10] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+            let count = rng.range(1, 5) as usize;
+            rng.distinct(count, 0, PRIMES.len() as u64).into_iter()
+                .map(|i| PRIMES[i as usize])
+                .map(|m| (rng.range(0, m) as i64, m as i64)).collect()
+        }
+
+        fn shrink_constraints(elements: &[(i64,i64)]) -> Vec<Vec<(i64,i64)>> {
+            let mut candidates = differential::remove_one(elements, 1);
+            for (i, &(r, m)) in elements.iter().enumerate() {
+                if r > 0 {
+                    let mut smaller = elements.to_vec();
+                    smaller[i] = (r - 1, m);
+                    candidates.push(smaller);
+                }
+            }
+            candidates
+        }
+
+        #[test]
+        fn differential() {
+            Differential::new("chinese remainder")
+                .implementation("original", |e: &Vec<(i64,i64)>| chinese_remainder_original(
+                    &e.iter().map(|&(r, _)| r).collect::<Vec<_>>(), &e.iter().map(|&(_, m)| m).collect::<Vec<_>>()))
+                .implementation("zipped", |e: &Vec<(i64,i64)>| chinese_remainder(e))
+                .implementation("brute force", |e: &Vec<(i64,i64)>| brute_force(e))
+                .check(200, 13, generate_constraints, |e| shrink_constraints(e));
+        }
     }
 }
 
@@ -105,7 +204,7 @@ mod tests {
     }
 
     parameterized_test::create!{find_timestamp, (routes, expected), {
-        assert_eq!(find_timestamp(&routes), expected);
+        assert_eq!(find_timestamp(&routes), Some(expected));
     }}
     find_timestamp!{
       a: ([Some(7),Some(13),None,None,Some(59),None,Some(31),Some(19)], 1068781),
@@ -117,8 +216,18 @@ mod tests {
 
     }
 
+    #[test]
+    fn not_coprime() {
+        assert_eq!(find_timestamp(&[Some(4), Some(6)]), None);
+    }
+
     #[test]
     fn parse_file() {
-        parse_data();
+        parse_data(include_str!("../data/day13.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Schedule>("generated schedules", 200, 1..=20);
     }
 }
@@ -1,12 +1,34 @@
 use crate::parsing::{regex_captures, capture_group};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
-use anyhow::{Error, Result, bail};
+use anyhow::{Error, Result, bail, ensure};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let instructions = parse_data().unwrap();
-    println!("Memory sum using V1 masks: {}", run_v1(&instructions).values().sum::<i64>());
-    println!("Memory sum using V2 masks: {}", run_v2(&instructions).values().sum::<i64>());
+pub struct DockingProgram(Vec<Instruction>);
+
+impl Solution for DockingProgram {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(DockingProgram(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let sum = run_v1(&self.0).values().sum::<i64>();
+        Console::detail(format!("Memory sum using V1 masks: {}", sum));
+        Ok(sum.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let sum = run_v2(&self.0).values().sum::<i64>();
+        Console::detail(format!("Memory sum using V2 masks: {}", sum));
+        Ok(sum.into())
+    }
+
+    /// Generates `size` instructions.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_program(rng, size)
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -102,8 +124,47 @@ fn run_v2(instructions: &[Instruction]) -> HashMap<i64, i64> {
     memory
 }
 
-fn parse_data() -> Result<Vec<Instruction>> {
-    include_str!("../data/day14.txt").trim().split("\n").map(|s|s.parse()).collect::<Result<Vec<_>>>()
+// Writes to a small range of addresses so they overlap, and applies each mask a character at a time
+// rather than through Mask's bit fields. Masks float at most six bits, like real programs' nine.
+fn generate_program(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 instructions");
+    let (mut v1, mut v2) = (BTreeMap::new(), BTreeMap::new());
+    let mut mask: Vec<char> = Vec::new();
+    let mut lines = Vec::with_capacity(size);
+    for i in 0..size {
+        if i == 0 || rng.chance(1, 5) {
+            mask = (0..36).map(|_| if rng.chance(1, 2) { '0' } else { '1' }).collect();
+            let floating = rng.range(0, 7) as usize;
+            for bit in rng.distinct(floating, 0, 36) {
+                mask[bit as usize] = 'X';
+            }
+            lines.push(format!("mask = {}", mask.iter().collect::<String>()));
+            continue;
+        }
+        let (addr, value) = (rng.range(0, 1 << 10), rng.range(0, 1 << 36));
+        lines.push(format!("mem[{}] = {}", addr, value));
+        // Bits are numbered from the right, and masks are written from the left
+        let bits = |n: u64| (0..36).rev().map(move |b| n >> b & 1);
+        let masked = mask.iter().zip(bits(value)).fold(0, |n, (&m, b)| n * 2 + if m == 'X' { b } else { m as u64 - '0' as u64 });
+        v1.insert(addr, masked);
+        let mut addrs = vec!(0);
+        for (&m, b) in mask.iter().zip(bits(addr)) {
+            addrs = match m {
+                '0' => addrs.iter().map(|a| a * 2 + b).collect(),
+                '1' => addrs.iter().map(|a| a * 2 + 1).collect(),
+                _ => addrs.iter().flat_map(|a| vec!(a * 2, a * 2 + 1)).collect(),
+            };
+        }
+        for a in addrs { v2.insert(a, value); }
+    }
+    let (part1, part2) = (v1.values().sum::<u64>(), v2.values().sum::<u64>());
+    Ok(Generated{ input: lines.join("\n") + "\n", part1: part1.into(), part2: part2.into() })
+}
+
+fn parse_data(input: &str) -> Result<Vec<Instruction>> {
+    let instructions = input.trim().split("\n").map(|s|s.parse()).collect::<Result<Vec<_>>>()?;
+    ensure!(matches!(instructions.first(), Some(Instruction::Mask(_))), "Program must start by setting a mask");
+    Ok(instructions)
 }
 
 #[cfg(test)]
@@ -131,6 +192,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day14.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<DockingProgram>("generated programs", 20, 1..=200);
     }
 }
@@ -1,12 +1,82 @@
-pub fn advent() {
-    // https://old.reddit.com/r/adventofcode/comments/kdfvec/2020_day_15_theory_behind_the_problem/
-    // https://oeis.org/A181391
-    let seed = vec!(12,1,16,3,11,0);
-    println!("2020: {}", memory_mapped_fast(&seed, 2020));
-    println!("30000000: {}", memory_mapped_fast(&seed, 30000000));
+use anyhow::{ensure, Context, Result};
+use crate::budget;
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
+
+// https://old.reddit.com/r/adventofcode/comments/kdfvec/2020_day_15_theory_behind_the_problem/
+// https://oeis.org/A181391
+pub struct MemoryGame(Vec<usize>);
+
+impl Solution for MemoryGame {
+    fn parse(input: &str) -> Result<Self> {
+        let seed: Vec<_> = input.trim().split(',').map(|n| n.parse()).collect::<Result<_, _>>()?;
+        validate(&seed)?;
+        Ok(MemoryGame(seed))
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "seed", kind: Kind::Numbers, default: None,
+               help: "The starting numbers to use instead of the puzzle input" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(seed) = options.numbers("seed") {
+            validate(seed)?;
+            self.0 = seed.to_vec();
+        }
+        Ok(())
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let spoken = memory_mapped_fast(&self.0, 2020)?;
+        Console::detail(format!("2020: {}", spoken));
+        Ok(spoken.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let spoken = memory_mapped_fast(&self.0, 30000000)?;
+        Console::detail(format!("30000000: {}", spoken));
+        Ok(spoken.into())
+    }
+
+    /// Generates `size` starting numbers.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_seed(rng, size)
+    }
+}
+
+// Like puzzle inputs, the starting numbers are distinct. Part 1 is found by searching back through
+// every turn, and part 2 by tracking the turn each number was last spoken (rather than the turn
+// before that, as memory_mapped_fast() does).
+fn generate_seed(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=20).contains(&size), "Size should be between 1 and 20 starting numbers");
+    let seed: Vec<_> = rng.distinct(size, 0, 20).into_iter().map(|n| n as usize).collect();
+    let part1 = memory_search(&seed, 2020);
+    let target = 30000000;
+    let mut last_spoken = vec![0_u32; target];
+    for (turn, &n) in seed[..seed.len()-1].iter().enumerate() {
+        last_spoken[n] = turn as u32 + 1;
+    }
+    let mut current = seed[seed.len()-1];
+    for turn in seed.len()..target {
+        let next = match last_spoken[current] { 0 => 0, last => turn - last as usize };
+        last_spoken[current] = turn as u32;
+        current = next;
+    }
+    let input = seed.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",");
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: current.into() })
+}
+
+// Both parts' targets must be past the end of the seed, and larger than any number in it
+fn validate(seed: &[usize]) -> Result<()> {
+    ensure!(!seed.is_empty() && seed.len() < 2020, "Must start with between 1 and 2019 numbers");
+    ensure!(seed.iter().all(|&n| n < 2020), "Starting numbers must be less than 2020: {:?}", seed);
+    Ok(())
 }
 
-fn memory_mapped_fast(seed: &[usize], target: usize) -> usize {
+fn memory_mapped_fast(seed: &[usize], target: usize) -> Result<usize> {
     let mut seen = vec![None; target];
     for (i, &v) in seed.into_iter().enumerate() {
         seen[v] = Some(i);
@@ -15,6 +85,9 @@ fn memory_mapped_fast(seed: &[usize], target: usize) -> usize {
     let mut prior = None;
     let mut result = None;
     for i in seed.len()..target {
+        if i % (1 << 16) == 0 {
+            budget::check().with_context(|| format!("Spoke {} of {} numbers", i, target))?;
+        }
         let value = match prior {
             Some(prior) => i-prior-1,
             None => 0,
@@ -23,7 +96,7 @@ fn memory_mapped_fast(seed: &[usize], target: usize) -> usize {
         seen[value] = Some(i);
         result = Some(value);
     }
-    result.unwrap()
+    Ok(result.unwrap())
 }
 
 #[cfg(test)]
@@ -42,8 +115,7 @@ fn memory_mapped(seed: &[usize], target: usize) -> usize {
     result.unwrap()
 }
 
-#[cfg(test)]
-pub fn memory_search(seed: &[usize], target: usize) -> usize {
+fn memory_search(seed: &[usize], target: usize) -> usize {
     let mut turns: Vec<_> = seed.iter().cloned().collect();
     while turns.len() < target {
         let last = turns[turns.len()-1];
@@ -59,11 +131,12 @@ pub fn memory_search(seed: &[usize], target: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{self, Differential, Rng};
 
     parameterized_test::create!{mem2020, (seed, expected), {
         assert_eq!(memory_search(&seed, 2020), expected);
         assert_eq!(memory_mapped(&seed, 2020), expected);
-        assert_eq!(memory_mapped_fast(&seed, 2020), expected);
+        assert_eq!(memory_mapped_fast(&seed, 2020).unwrap(), expected);
     }}
     mem2020!{
       a: ([0,3,6], 436),
@@ -76,7 +149,7 @@ mod tests {
     }
 
     parameterized_test::create!{mem30000000, (seed, expected), {
-        assert_eq!(memory_mapped_fast(&seed, 30000000), expected);
+        assert_eq!(memory_mapped_fast(&seed, 30000000).unwrap(), expected);
     }}
     mem30000000!{
       a: ([0,3,6], 175594),
@@ -87,4 +160,46 @@ mod tests {
       f: ([3,2,1], 18),
       g: ([3,1,2], 362),
     }
+
+    #[test]
+    fn over_budget() {
+        let game = MemoryGame(vec!(0, 3, 6));
+        let error = budget::within(Some(std::time::Duration::from_millis(10)), || game.part2()).unwrap_err();
+        assert!(budget::TimedOut::caused(&error), "{:#}", error);
+        assert!(format!("{:#}", error).starts_with("Spoke "), "{:#}", error);
+    }
+
+    type Game = (Vec<usize>, usize);
+
+    // Like puzzle inputs, the starting numbers are distinct
+    fn generate_game(rng: &mut Rng) -> Game {
+        let len = rng.range(1, 7) as usize;
+        let seed: Vec<_> = rng.distinct(len, 0, 10).into_iter().map(|n| n as usize).collect();
+        let target = rng.range(seed.len() as u64 + 10, 300) as usize;
+        (seed, target)
+    }
+
+    fn shrink_game((seed, target): &Game) -> Vec<Game> {
+        let mut candidates: Vec<_> = differential::remove_one(seed, 1).into_iter().map(|s| (s, *target)).collect();
+        if *target > seed.len() + 10 {
+            candidates.push((seed.clone(), (*target / 2).max(seed.len() + 10)));
+            candidates.push((seed.clone(), target - 1));
+        }
+        candidates
+    }
+
+    #[test]
+    fn differential() {
+        Differential::new("memory game")
+            .implementation("search", |(seed, target): &Game| memory_search(seed, *target))
+            .implementation("mapped", |(seed, target): &Game| memory_mapped(seed, *target))
+            .implementation("mapped_fast", |(seed, target): &Game| memory_mapped_fast(seed, *target).unwrap())
+            .check(200, 15, generate_game, shrink_game);
+    }
+
+    // Each case plays 30000000 turns three times, so only one is checked
+    #[test]
+    fn generated() {
+        differential::check_generator::<MemoryGame>("generated games", 1, 1..=6);
+    }
 }
@@ -1,35 +1,57 @@
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use anyhow::{Result, Error, Context};
+use crate::generate::{Generated, Rng};
 use crate::parsing::{regex_captures, capture_group};
+use crate::console::Console;
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let mut data = parse_data().unwrap();
-    println!("Error Rate: {}", data.remove_invalid_tickets());
-    let labels = data.label_columns().unwrap();
-    println!("Departure Product: {}", labels.iter()
-        .filter(|(l, _)| l.starts_with("departure")).map(|(_, &c)| data.ticket[c]).product::<i64>());
+impl Solution for TicketData {
+    fn parse(input: &str) -> Result<Self> {
+        input.trim().parse()
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let error_rate = self.error_rate();
+        Console::detail(format!("Error Rate: {}", error_rate));
+        Ok(error_rate.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let labels = self.label_columns().map_err(Failure::no_solution)?;
+        let product = labels.iter()
+            .filter(|(l, _)| l.starts_with("departure")).map(|(_, &c)| self.ticket[c]).product::<i64>();
+        Console::detail(format!("Departure Product: {}", product));
+        Ok(product.into())
+    }
+
+    /// Generates notes on `size` fields.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_notes(rng, size)
+    }
 }
 
-struct TicketData {
+pub struct TicketData {
     rules: HashMap<String, Box<dyn Fn(i64)->bool>>,
     ticket: Vec<i64>,
     other_tickets: Vec<Vec<i64>>,
 }
 
 impl TicketData {
+    fn valid_cell(&self, cell: i64) -> bool {
+        self.rules.values().any(|r| r(cell))
+    }
+
+    fn error_rate(&self) -> i64 {
+        self.other_tickets.iter().flat_map(|t| t.iter()).filter(|&&c| !self.valid_cell(c)).sum()
+    }
+
+    #[cfg(test)]
     fn remove_invalid_tickets(&mut self) -> i64 {
+        let error_rate = self.error_rate();
         let unknown_tickets: Vec<_> = self.other_tickets.drain(..).collect();
-        let mut error_rate = 0;
         for ticket in unknown_tickets {
-            let mut valid = true;
-            for cell in ticket.iter() {
-                if !self.rules.values().any(|r| r(*cell)) {
-                    valid = false;
-                    error_rate += cell;
-                }
-            }
-            if valid {
+            if ticket.iter().all(|&c| self.valid_cell(c)) {
                 self.other_tickets.push(ticket);
             }
         }
@@ -37,8 +59,11 @@ impl TicketData {
     }
 
     // Nice visualization: https://old.reddit.com/r/adventofcode/comments/ke3ypd/
+    // Invalid tickets are ignored.
     fn label_columns(&self) -> Result<HashMap<String, usize>> {
-        let columns: Vec<Vec<_>> = (0..self.ticket.len()).map(|i| self.other_tickets.iter().map(|t| t[i]).collect()).collect();
+        let valid_tickets: Vec<_> = self.other_tickets.iter()
+            .filter(|t| t.iter().all(|&c| self.valid_cell(c))).collect();
+        let columns: Vec<Vec<_>> = (0..self.ticket.len()).map(|i| valid_tickets.iter().map(|t| t[i]).collect()).collect();
         let mut candidates: HashMap<&str, HashSet<usize>> = HashMap::new();
 
         for (label, rule) in self.rules.iter() {
@@ -67,6 +92,78 @@ impl TicketData {
     }
 }
 
+const FIELDS: &[&str] = &[
+    "departure location", "departure station", "departure platform", "departure track", "departure date",
+    "departure time", "arrival location", "arrival station", "arrival platform", "arrival track", "class",
+    "duration", "price", "route", "row", "seat", "train", "type", "wagon", "zone"];
+
+// The valid values for one field: low-a or b-high
+#[derive(Copy, Clone)]
+struct Ranges { low: i64, a: i64, b: i64, high: i64 }
+
+impl Ranges {
+    fn contains(&self, n: i64) -> bool {
+        (self.low..=self.a).contains(&n) || (self.b..=self.high).contains(&n)
+    }
+
+    fn pick(&self, rng: &mut Rng) -> i64 {
+        let (low, high) = if rng.chance(1, 2) { (self.low, self.a) } else { (self.b, self.high) };
+        rng.range(low as u64, high as u64 + 1) as i64
+    }
+}
+
+// Each field's valid values are a subset of the previous field's, and every column has a value its
+// field accepts but the next field doesn't. So the last field matches only its own column, the
+// one before it that column and its own, and so on, meaning there's exactly one assignment.
+fn generate_notes(rng: &mut Rng, size: usize) -> Result<Generated> {
+    anyhow::ensure!((1..=40).contains(&size), "Size should be between 1 and 40 fields");
+    let mut names: Vec<_> = FIELDS.iter().map(|f| f.to_string()).chain((FIELDS.len()+1..).map(|i| format!("field {}", i)))
+        .take(size).collect();
+    rng.shuffle(&mut names);
+    // Each field narrows the previous one by a few values on every side; 40 fields still leave a gap
+    let ranges: Vec<_> = (0..size as i64).map(|k| Ranges{
+        low: 10 + 4*k + rng.range(0, 4) as i64, a: 480 - 4*k - rng.range(0, 4) as i64,
+        b: 520 + 4*k + rng.range(0, 4) as i64, high: 990 - 4*k - rng.range(0, 4) as i64 }).collect();
+    // columns[i] is the field (and ranges) of column i
+    let mut columns: Vec<_> = (0..size).collect();
+    rng.shuffle(&mut columns);
+
+    let valid_ticket = |rng: &mut Rng| columns.iter().map(|&f| ranges[f].pick(rng)).collect::<Vec<_>>();
+    let ticket = valid_ticket(rng);
+    let mut nearby: Vec<_> = (0..3*size+5).map(|_| valid_ticket(rng)).collect();
+    for (column, &field) in columns.iter().enumerate() {
+        if let Some(next) = ranges.get(field + 1) {
+            let distinct = loop {
+                let n = ranges[field].pick(rng);
+                if !next.contains(n) { break n; }
+            };
+            let row = rng.range(0, nearby.len() as u64) as usize;
+            nearby[row][column] = distinct;
+        }
+    }
+    // Values no field accepts: in the first (and so every) field's gap, or above every range
+    let mut error_rate = 0;
+    for _ in 0..size/4+2 {
+        let mut ticket = valid_ticket(rng);
+        let invalid = if rng.chance(1, 2) { rng.range(ranges[0].a as u64 + 1, ranges[0].b as u64) }
+                      else { rng.range(ranges[0].high as u64 + 1, 1000) } as i64;
+        ticket[rng.range(0, size as u64) as usize] = invalid;
+        error_rate += invalid;
+        let row = rng.range(0, nearby.len() as u64 + 1) as usize;
+        nearby.insert(row, ticket);
+    }
+
+    let departures = columns.iter().enumerate()
+        .filter(|(_, &f)| names[f].starts_with("departure")).map(|(c, _)| ticket[c]).product::<i64>();
+    let row = |t: &Vec<i64>| t.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",");
+    let mut input: Vec<_> = names.iter().zip(ranges.iter())
+        .map(|(name, r)| format!("{}: {}-{} or {}-{}", name, r.low, r.a, r.b, r.high)).collect();
+    rng.shuffle(&mut input);
+    input.push(format!("\nyour ticket:\n{}\n\nnearby tickets:", row(&ticket)));
+    input.extend(nearby.iter().map(row));
+    Ok(Generated{ input: input.join("\n") + "\n", part1: error_rate.into(), part2: departures.into() })
+}
+
 impl FromStr for TicketData {
     type Err = Error;
     fn from_str(data: &str) -> Result<Self> {
@@ -97,10 +194,6 @@ impl FromStr for TicketData {
     }
 }
 
-fn parse_data() -> Result<TicketData> {
-    include_str!("../data/day16.txt").trim().parse()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,6 +227,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        TicketData::parse(include_str!("../data/day16.txt")).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<TicketData>("generated ticket notes", 20, 1..=20);
+    }
+}
@@ -1,9 +1,50 @@
 use std::collections::HashSet;
+use std::convert::TryFrom;
+use anyhow::{bail, ensure, Context, Result};
+use crate::budget;
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let points = parse_data();
-    println!("3D space: {}", Simulator3D{}.cycles(6, &points).len());
-    println!("4D space: {}", Simulator4D{}.cycles(6, &points).len());
+pub struct PocketDimension {
+    active: HashSet<Point>,
+    cycles: u32,
+}
+
+impl Solution for PocketDimension {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(PocketDimension{ active: to_set(input.trim())?, cycles: 6 })
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "cycles", kind: Kind::Number{ min: 0 }, default: Some("6"),
+               help: "How many cycles to simulate" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(cycles) = options.number("cycles") {
+            self.cycles = u32::try_from(cycles).context("--cycles is too large")?;
+        }
+        Ok(())
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let active = Simulator3D{}.cycles(self.cycles, &self.active)?.len();
+        Console::detail(format!("3D space: {}", active));
+        Ok(active.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let active = Simulator4D{}.cycles(self.cycles, &self.active)?.len();
+        Console::detail(format!("4D space: {}", active));
+        Ok(active.into())
+    }
+
+    /// Generates a `size` by `size` starting slice.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_slice(rng, size)
+    }
 }
 
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -42,12 +83,14 @@ trait Simulator {
         ret
     }
 
-    fn cycles(&self, rounds: u32, points: &HashSet<Point>) -> HashSet<Point> {
+    fn cycles(&self, rounds: u32, points: &HashSet<Point>) -> Result<HashSet<Point>> {
         let mut points = points.clone();
-        for _ in 0..rounds {
+        for round in 0..rounds {
+            budget::check()
+                .with_context(|| format!("Simulated {} of {} cycles; {} cubes active", round, rounds, points.len()))?;
             points = self.round(&points);
         }
-        points
+        Ok(points)
     }
 }
 
@@ -85,22 +128,57 @@ impl Simulator for Simulator4D {
     }
 }
 
-fn to_set(str: &str) -> HashSet<Point> {
+// Activates cubes at random, and runs the default six cycles over a dense array big enough for them
+// to spread into, rather than over sets of neighbors.
+fn generate_slice(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=20).contains(&size), "Size should be between 1 and 20 rows");
+    let slice: Vec<Vec<bool>> = (0..size).map(|_| (0..size).map(|_| rng.chance(1, 2)).collect()).collect();
+    let input = slice.iter()
+        .map(|row| row.iter().map(|&a| if a { '#' } else { '.' }).collect::<String>())
+        .collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: simulate_dense(&slice, false).into(), part2: simulate_dense(&slice, true).into() })
+}
+
+fn simulate_dense(slice: &[Vec<bool>], four_d: bool) -> usize {
+    const CYCLES: i32 = 6;
+    let side = slice.len() as i32 + 2 * CYCLES;
+    let depth = 2 * CYCLES + 1;
+    let dims = [side, side, depth, if four_d { depth } else { 1 }];
+    let cube = |low: i32, dims: [i32; 4]| (low..dims[3]).flat_map(move |w| (low..dims[2]).flat_map(move |z|
+        (low..dims[1]).flat_map(move |y| (low..dims[0]).map(move |x| [x, y, z, w])))).collect::<Vec<_>>();
+    let cells = cube(0, dims);
+    let offsets: Vec<_> = cube(-1, [2, 2, 2, if four_d { 2 } else { 1 }]).into_iter().filter(|o| o != &[0; 4]).collect();
+    let index = |p: [i32; 4]| (((p[3] * dims[2] + p[2]) * dims[1] + p[1]) * dims[0] + p[0]) as usize;
+    let mut active = vec![false; cells.len()];
+    for (y, row) in slice.iter().enumerate() {
+        for (x, &a) in row.iter().enumerate() {
+            active[index([x as i32 + CYCLES, y as i32 + CYCLES, CYCLES, dims[3] / 2])] = a;
+        }
+    }
+    for _ in 0..CYCLES {
+        active = cells.iter().map(|&p| {
+            let count = offsets.iter()
+                .map(|o| [p[0] + o[0], p[1] + o[1], p[2] + o[2], p[3] + o[3]])
+                .filter(|q| (0..4).all(|i| q[i] >= 0 && q[i] < dims[i]) && active[index(*q)])
+                .count();
+            count == 3 || (count == 2 && active[index(p)])
+        }).collect();
+    }
+    active.iter().filter(|&&a| a).count()
+}
+
+fn to_set(str: &str) -> Result<HashSet<Point>> {
     let mut ret = HashSet::new();
     for (y, line) in str.split("\n").enumerate() {
         for (x, c) in line.chars().enumerate() {
             match c {
                 '#' => { ret.insert(point(x as i32, y as i32, 0, 0)); },
                 '.' => {},
-                _ => panic!(),
+                _ => bail!("Unexpected character {:?} at {},{}", c, x, y),
             }
         }
     }
-    ret
-}
-
-fn parse_data() -> HashSet<Point> {
-    to_set(include_str!("../data/day17.txt").trim())
+    Ok(ret)
 }
 
 #[cfg(test)]
@@ -111,13 +189,19 @@ mod tests {
 
     #[test]
     fn example3d() {
-        let points = to_set(EXAMPLE);
-        assert_eq!(Simulator3D{}.cycles(6, &points).len(), 112);
+        let points = to_set(EXAMPLE).unwrap();
+        assert_eq!(Simulator3D{}.cycles(6, &points).unwrap().len(), 112);
     }
 
     #[test]
     fn example4d() {
-        let points = to_set(EXAMPLE);
-        assert_eq!(Simulator4D{}.cycles(6, &points).len(), 848);
+        let points = to_set(EXAMPLE).unwrap();
+        assert_eq!(Simulator4D{}.cycles(6, &points).unwrap().len(), 848);
+    }
+
+    // Simulating 4D space is slow, so only one small slice is checked
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<PocketDimension>("generated slices", 1, 1..=3);
     }
 }
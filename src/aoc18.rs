@@ -1,11 +1,94 @@
-use anyhow::{Context, Result};
-
-pub fn advent() {
-    let tokens = parse_data().unwrap();
-    println!("Left-to-Right: {}",
-             tokens.iter().map(|t| t.simple_expression().unwrap().evaluate()).sum::<i64>());
-    println!("Addition first: {}",
-             tokens.iter().map(|t| t.ordered_expression().unwrap().evaluate()).sum::<i64>());
+use anyhow::{ensure, Context, Result};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
+
+pub struct Homework(Vec<String>);
+
+impl Homework {
+    fn evaluate(&self, to_expression: impl Fn(&Tokens) -> Result<Expression>) -> Result<i64> {
+        let mut sum: i64 = 0;
+        for line in self.0.iter() {
+            let value = to_expression(&Tokens::parse(line)?)?.evaluate()
+                .with_context(|| format!("Overflowed evaluating {}", line))?;
+            sum = sum.checked_add(value).context("Sum overflowed")?;
+        }
+        Ok(sum)
+    }
+}
+
+impl Solution for Homework {
+    fn parse(input: &str) -> Result<Self> {
+        // Both parts share a grammar, so parts can't fail on a line that parses here
+        for (i, tokens) in parse_data(input)?.iter().enumerate() {
+            tokens.simple_expression().with_context(|| format!("Line {}: {}", i + 1, tokens.0.join(" ")))?;
+        }
+        Ok(Homework(input.trim().split("\n").map(|l| l.to_string()).collect()))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let sum = self.evaluate(|t| t.simple_expression())?;
+        Console::detail(format!("Left-to-Right: {}", sum));
+        Ok(sum.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let sum = self.evaluate(|t| t.ordered_expression())?;
+        Console::detail(format!("Addition first: {}", sum));
+        Ok(sum.into())
+    }
+
+    /// Generates `size` lines of homework.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_homework(rng, size)
+    }
+}
+
+// Builds each expression's text and both of its values together, without going through Tokens or
+// Expression. Lines are redrawn if either value is over a trillion, so the sums can't overflow.
+fn generate_homework(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 lines");
+    let (mut lines, mut part1, mut part2) = (Vec::with_capacity(size), 0, 0);
+    while lines.len() < size {
+        if let Some((line, simple, ordered)) = generate_expression(rng, 2) {
+            if simple <= 1_000_000_000_000 && ordered <= 1_000_000_000_000 {
+                lines.push(line);
+                part1 += simple;
+                part2 += ordered;
+            }
+        }
+    }
+    Ok(Generated{ input: lines.join("\n") + "\n", part1: part1.into(), part2: part2.into() })
+}
+
+// Returns the expression, its left-to-right value, and its value with addition first
+fn generate_expression(rng: &mut Rng, depth: u32) -> Option<(String, i64, i64)> {
+    let mut text = String::new();
+    let (mut simple, mut product, mut sum) = (0_i64, 1_i64, 0_i64);
+    for i in 0..rng.range(2, 6) {
+        let (term, term_simple, term_ordered) = if depth > 0 && rng.chance(1, 4) {
+            let (inner, inner_simple, inner_ordered) = generate_expression(rng, depth - 1)?;
+            (format!("({})", inner), inner_simple, inner_ordered)
+        } else {
+            let n = rng.range(1, 10) as i64;
+            (n.to_string(), n, n)
+        };
+        if i == 0 {
+            text = term;
+            simple = term_simple;
+            sum = term_ordered;
+        } else if rng.chance(1, 2) {
+            text = format!("{} + {}", text, term);
+            simple = simple.checked_add(term_simple)?;
+            sum = sum.checked_add(term_ordered)?;
+        } else {
+            text = format!("{} * {}", text, term);
+            simple = simple.checked_mul(term_simple)?;
+            product = product.checked_mul(sum)?;
+            sum = term_ordered;
+        }
+    }
+    Some((text, simple, product.checked_mul(sum)?))
 }
 
 struct Tokens<'a>(Vec<&'a str>);
@@ -13,6 +96,7 @@ struct Tokens<'a>(Vec<&'a str>);
 impl<'a> Tokens<'a> {
     // Can't impl FromStr: https://stackoverflow.com/q/28931515/113632
     fn parse(str: &'a str) -> Result<Self> {
+        anyhow::ensure!(str.is_ascii(), "Unexpected non-ASCII characters: {}", str);
         let mut ret = Vec::new();
         let mut num_start = None;
         for i in 0..str.len() {
@@ -40,9 +124,10 @@ impl<'a> Tokens<'a> {
 
         // Constructs an expression from the fewest possible tokens
         fn next_value<'a>(tokens: &'a[&'a str]) -> Result<(Expression, &'a[&'a str])> {
+            anyhow::ensure!(!tokens.is_empty(), "Expected a value, found the end of the expression");
             if tokens[0] == "(" {
                 let (op, remainder) = partial_parse(&tokens[1..])?;
-                anyhow::ensure!(!remainder.is_empty());
+                anyhow::ensure!(!remainder.is_empty(), "Unclosed '(' before {:?}", op);
                 anyhow::ensure!(remainder[0] == ")", "Unexpected remainder {:?} after parsing {:?}", remainder, op);
                 Ok((op, &remainder[1..]))
             } else {
@@ -53,7 +138,7 @@ impl<'a> Tokens<'a> {
 
         // Constructs an expression from as many tokens as possible, halting at an unmatched ')'
         fn partial_parse<'a>(tokens: &'a[&'a str]) -> Result<(Expression, &'a[&'a str])> {
-            anyhow::ensure!(!tokens.is_empty());
+            anyhow::ensure!(!tokens.is_empty(), "Expected an expression, found the end of the input");
             let (mut expr, mut tokens) = next_value(tokens)?;
             while !tokens.is_empty() && tokens[0] != ")" {
                 let op = tokens[0];
@@ -69,7 +154,7 @@ impl<'a> Tokens<'a> {
         }
 
         let (expr, remainder) = partial_parse(&self.0)?;
-        anyhow::ensure!(remainder.is_empty(), "Unparsed prefix: {:?} - parsed: {:?}", remainder, expr);
+        anyhow::ensure!(remainder.is_empty(), "Unmatched ')' in {:?} - parsed: {:?}", remainder, expr);
         Ok(expr)
     }
 
@@ -80,9 +165,10 @@ impl<'a> Tokens<'a> {
         // This is identical to simple_expression(), it'd be nice to refactor so that we don't
         // redefine it, but notice this calls a different partial_parse() function.
         fn next_value<'a>(tokens: &'a[&'a str]) -> Result<(Expression, &'a[&'a str])> {
+            anyhow::ensure!(!tokens.is_empty(), "Expected a value, found the end of the expression");
             if tokens[0] == "(" {
                 let (op, remainder) = partial_parse(&tokens[1..])?;
-                anyhow::ensure!(!remainder.is_empty());
+                anyhow::ensure!(!remainder.is_empty(), "Unclosed '(' before {:?}", op);
                 anyhow::ensure!(remainder[0] == ")", "Unexpected remainder {:?} after parsing {:?}", remainder, op);
                 Ok((op, &remainder[1..]))
             } else {
@@ -93,7 +179,7 @@ impl<'a> Tokens<'a> {
 
         // Constructs an expression from as many tokens as possible, halting at an unmatched ')'
         fn partial_parse<'a>(tokens: &'a[&'a str]) -> Result<(Expression, &'a[&'a str])> {
-            anyhow::ensure!(!tokens.is_empty());
+            anyhow::ensure!(!tokens.is_empty(), "Expected an expression, found the end of the input");
             let (mut expr, mut tokens) = next_value(tokens)?;
             while !tokens.is_empty() && tokens[0] != ")" {
                 let op = tokens[0];
@@ -115,7 +201,7 @@ impl<'a> Tokens<'a> {
         }
 
         let (expr, remainder) = partial_parse(&self.0)?;
-        anyhow::ensure!(remainder.is_empty(), "Unparsed prefix: {:?} - parsed: {:?}", remainder, expr);
+        anyhow::ensure!(remainder.is_empty(), "Unmatched ')' in {:?} - parsed: {:?}", remainder, expr);
         Ok(expr)
     }
 }
@@ -128,18 +214,20 @@ enum Expression {
 }
 
 impl Expression {
-    fn evaluate(&self) -> i64 {
+    // None if the result doesn't fit in an i64
+    fn evaluate(&self) -> Option<i64> {
         use crate::aoc18::Expression::*;
         match self {
-            Literal(n) => *n,
-            Addition(l, r) => l.evaluate() + r.evaluate(),
-            Multiplication(l, r) => l.evaluate() * r.evaluate(),
+            Literal(n) => Some(*n),
+            Addition(l, r) => l.evaluate()?.checked_add(r.evaluate()?),
+            Multiplication(l, r) => l.evaluate()?.checked_mul(r.evaluate()?),
         }
     }
 }
 
-fn parse_data() -> Result<Vec<Tokens<'static>>> {
-    include_str!("../data/day18.txt").trim().split("\n").map(|e| Tokens::parse(e)).collect()
+fn parse_data(input: &str) -> Result<Vec<Tokens<'_>>> {
+    input.trim().split("\n").enumerate()
+        .map(|(i, e)| Tokens::parse(e).with_context(|| format!("Line {}", i + 1))).collect()
 }
 
 #[cfg(test)]
@@ -148,7 +236,7 @@ mod tests {
 
     parameterized_test::create!{simple, (expression, expected), {
         let tokens = Tokens::parse(expression).unwrap();
-        assert_eq!(tokens.simple_expression().unwrap().evaluate(), expected);
+        assert_eq!(tokens.simple_expression().unwrap().evaluate(), Some(expected));
     }}
     simple!{
       a: ("1 + 2 * 3 + 4 * 5 + 6", 71),
@@ -161,7 +249,7 @@ mod tests {
 
     parameterized_test::create!{ordered, (expression, expected), {
         let tokens = Tokens::parse(expression).unwrap();
-        assert_eq!(tokens.ordered_expression().unwrap().evaluate(), expected);
+        assert_eq!(tokens.ordered_expression().unwrap().evaluate(), Some(expected));
     }}
     ordered!{
       a: ("1 + 2 * 3 + 4 * 5 + 6", 231),
@@ -172,8 +260,31 @@ mod tests {
       f: ("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 23340),
     }
 
+    parameterized_test::create!{malformed, (input, error), {
+        let message = format!("{:#}", Homework::parse(input).err().unwrap());
+        assert!(message.starts_with(error), "{}", message);
+    }}
+    malformed!{
+        trailing_operator: ("1 + 2\n1 +", "Line 2: 1 +: Expected a value, found the end of the expression"),
+        unclosed: ("1 + (2", "Line 1: 1 + ( 2: Unclosed '('"),
+        unmatched: ("1 + 2)", "Line 1: 1 + 2 ): Unmatched ')'"),
+        empty_parens: ("()", "Line 1: ( ): Unexpected token: )"),
+        operator: ("1 - 2", "Line 1: Unexpected character at 2: '-'"),
+    }
+
+    #[test]
+    fn overflow() {
+        let homework = Homework::parse("9999999999 * 9999999999").unwrap();
+        assert!(homework.part1().is_err());
+    }
+
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day18.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Homework>("generated homework", 20, 1..=100);
     }
 }
@@ -1,36 +1,86 @@
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use anyhow::{Error, Result};
+use anyhow::{anyhow, ensure, Context, Error, Result};
+use crate::budget;
 use crate::parsing;
 use regex::Regex;
+use crate::generate::{Generated, Rng};
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::solution::{Answer, Failure, Solution};
+
+pub struct Messages {
+    rules: Rules,
+    messages: Vec<String>,
+    depth: usize,
+}
+
+impl Solution for Messages {
+    fn parse(input: &str) -> Result<Self> {
+        let (rules, messages) = parse_data(input.trim())?;
+        // Empirically, 5-deep is sufficient to get the right answer. 8 deep causes Rust to fail after
+        // attempting to allocate ~28GB while evaluating the regex. At 15 it attempts to allocate
+        // ~318GB(!), any deeper and the compiled regex itself exceeds the ~10MB limit.
+        Ok(Messages{rules, messages, depth: 5})
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "depth", kind: Kind::Number{ min: 1 }, default: Some("5"),
+               help: "How many times to unroll the recursive rules when building part 2's regex" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(depth) = options.number("depth") {
+            self.depth = depth;
+        }
+        Ok(())
+    }
+
+    /// Generates a grammar and `size` messages.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_messages(rng, size)
+    }
 
-pub fn advent(args: &[String]) {
-    let (mut rules, expressions) = read_data().unwrap();
-    let regex = elapsed!("Construct regex", rules.to_regex().unwrap());
-
-    // This really isn't necessary (it still runs in less than a second) but it shrinks the number
-    // of rule elements by ~50% and does appear to improve speeds somewhat.
-    elapsed!(rules.reduce());
-
-    println!("Initially valid: {}", elapsed!("Initial", rules.check_all(&expressions).len()));
-    rules.make_recursive();
-    println!("With recursive rules: {}", elapsed!("Recursive", rules.check_all(&expressions).len()));
-
-    println!("-----");
-    println!("Initially valid (regex): {}",
-             elapsed!("Initial (regex)", expressions.iter().filter(|e| regex.is_match(e)).count()));
-    // Empirically, 5-deep is sufficient to get the right answer. 8 deep causes Rust to fail after
-    // attempting to allocate ~28GB while evaluating the regex. At 15 it attempts to allocate
-    // ~318GB(!), any deeper and the compiled regex itself exceeds the ~10MB limit.
-    let depth = args.get(0).map(|a| a.parse::<usize>().unwrap()).unwrap_or(5);
-    rules.make_pseduo_recursive(depth);
-    println!("With pseudo-recursive ({}) rules: {}", depth,
-             elapsed!("Pseduo-recursive", rules.check_all(&expressions).len()));
-    let recursive_regex = rules.to_regex().unwrap();
-    println!("With pseudo-recursive ({}) rules (regex): {}", depth,
-             elapsed!("Pseduo-recursive (regex)",
-             expressions.iter().filter(|e| recursive_regex.is_match(e)).count()));
+    fn part1(&self) -> Result<Answer> {
+        let mut rules = self.rules.clone();
+        let regex = span!("Construct regex", rules.to_regex()?);
+
+        // This really isn't necessary (it still runs in less than a second) but it shrinks the number
+        // of rule elements by ~50% and does appear to improve speeds somewhat.
+        span!(rules.reduce());
+
+        let valid = span!("Initial", rules.check_all(&self.messages)?.len());
+        let regex_valid =
+            span!("Initial (regex)", self.messages.iter().filter(|e| regex.is_match(e)).count());
+        Console::detail(format!("Initially valid: {}", valid));
+        Console::detail(format!("Initially valid (regex): {}", regex_valid));
+        ensure!(valid == regex_valid, "Rules matched {} messages but regex matched {}", valid, regex_valid);
+        Ok(valid.into())
+    }
 
+    fn part2(&self) -> Result<Answer> {
+        if !self.rules.rules.contains_key(&42) || !self.rules.rules.contains_key(&31) {
+            return Err(Failure::no_solution(anyhow!("Rules 8 and 11 can only be replaced if rules 42 and 31 are defined")));
+        }
+        // Reducing can inline and drop 42 and 31, so it's only done once they've been replaced (and
+        // not at all for the recursive rules, which it can't handle)
+        let mut recursive = self.rules.clone();
+        recursive.make_recursive();
+        let valid = span!("Recursive", recursive.check_all(&self.messages)?.len());
+
+        let mut rules = self.rules.clone();
+        rules.make_pseduo_recursive(self.depth);
+        span!(rules.reduce());
+        let pseudo_valid = span!("Pseduo-recursive", rules.check_all(&self.messages)?.len());
+        let recursive_regex = span!("Construct pseudo-recursive regex", rules.to_regex()?);
+        let regex_valid = span!("Pseduo-recursive (regex)",
+             self.messages.iter().filter(|e| recursive_regex.is_match(e)).count());
+        // Too shallow a --depth undercounts, so the pseudo-recursive counts are only reported
+        Console::detail(format!("With recursive rules: {}", valid));
+        Console::detail(format!("With pseudo-recursive ({}) rules: {}", self.depth, pseudo_valid));
+        Console::detail(format!("With pseudo-recursive ({}) rules (regex): {}", self.depth, regex_valid));
+        Ok(valid.into())
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -42,6 +92,15 @@ enum Rule {
 }
 
 impl Rule {
+    fn references(&self) -> HashSet<u32> {
+        match self {
+            Rule::Literal(_) => HashSet::new(),
+            Rule::Reference(id) => vec!(*id).into_iter().collect(),
+            Rule::Disjunction(rs) | Rule::Sequence(rs) =>
+                rs.iter().flat_map(|r| r.references().into_iter()).collect(),
+        }
+    }
+
     fn reduce(self) -> Rule {
         if let Rule::Sequence(parts) = self {
             let mut reduced = parts.into_iter().map(|r| r.reduce())
@@ -88,7 +147,7 @@ impl FromStr for Rule {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Rules {
     rules: HashMap<u32, Rule>,
 }
@@ -147,17 +206,8 @@ impl Rules {
     }
 
     fn gc(&mut self) {
-        fn visit(rule: &Rule) -> HashSet<u32> {
-            match rule {
-                Rule::Literal(_) => HashSet::new(),
-                Rule::Reference(id) => vec!(*id).into_iter().collect(),
-                Rule::Disjunction(rs) | Rule::Sequence(rs) =>
-                    rs.iter().flat_map(|r| visit(r).into_iter()).collect(),
-            }
-        }
-
         let mut referenced: HashSet<_> = self.rules.values()
-            .flat_map(|r| visit(r).into_iter()).collect();
+            .flat_map(|r| r.references().into_iter()).collect();
         referenced.insert(0);
 
         let garbage: Vec<_> = self.rules.keys()
@@ -206,7 +256,7 @@ impl Rules {
     // Checks the text against rule 0
     fn check(&self, text: &str) -> bool {
         fn check_id<'a>(slf: &Rules, rule_id: u32, text: &'a str) -> Vec<&'a str> {
-            // TODO return Result<>
+            // References are validated when parsing
             check(slf, slf.rules.get(&rule_id).expect("No such rule"), text)
         }
 
@@ -239,8 +289,13 @@ impl Rules {
         remainders.iter().find(|r| r.is_empty()).is_some()
     }
 
-    fn check_all<'a>(&self, all: &'a [String]) -> Vec<&'a String> {
-        all.iter().filter(|t| self.check(t)).collect()
+    fn check_all<'a>(&self, all: &'a [String]) -> Result<Vec<&'a String>> {
+        let mut valid = Vec::new();
+        for (i, text) in all.iter().enumerate() {
+            budget::check().with_context(|| format!("Checked {} of {} messages", i, all.len()))?;
+            if self.check(text) { valid.push(text); }
+        }
+        Ok(valid)
     }
 }
 
@@ -255,21 +310,99 @@ impl FromStr for Rules {
                 parsing::capture_group(&caps, 2).parse()?))
         }
         let rules = data.split("\n").map(|l| parse_rule(l)).collect::<Result<HashMap<_, _>>>()?;
-        anyhow::ensure!(rules.contains_key(&0));
+        anyhow::ensure!(rules.contains_key(&0), "No rule 0");
+        for (id, rule) in rules.iter() {
+            for reference in rule.references() {
+                anyhow::ensure!(rules.contains_key(&reference), "Rule {} refers to undefined rule {}", id, reference);
+            }
+        }
         Ok(Rules{rules})
     }
 }
 
+// Like the real inputs, rules 42 and 31 each match a disjoint set of fixed-length chunks, so
+// messages can be checked by classifying their chunks. Rule 0 is 8 11, i.e. 42 42 31, and once 8
+// and 11 are made recursive it's 42 repeated more times than 31.
+fn generate_messages(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=1000).contains(&size), "Size should be between 1 and 1000 messages");
+    let len = rng.range(3, 6) as usize;
+    let (chunks42, chunks31) = loop {
+        let (mut chunks42, mut chunks31) = (Vec::new(), Vec::new());
+        for i in 0..1 << len {
+            let chunk: String = (0..len).rev().map(|b| if i >> b & 1 == 1 { 'b' } else { 'a' }).collect();
+            if rng.chance(1, 2) { chunks42.push(chunk) } else { chunks31.push(chunk) }
+        }
+        if !chunks42.is_empty() && !chunks31.is_empty() { break (chunks42, chunks31); }
+    };
+
+    // Each rule matches a set of suffixes: the first letter, followed by a rule for the remainders
+    fn trie(suffixes: &[&str], id: u32, ids: &mut Vec<u32>, letters: &[u32; 2], rules: &mut Vec<String>) {
+        let mut alternatives = Vec::new();
+        for (letter, &letter_id) in ['a', 'b'].iter().zip(letters.iter()) {
+            let rest: Vec<_> = suffixes.iter().filter(|s| s.starts_with(*letter)).map(|s| &s[1..]).collect();
+            if rest.is_empty() { continue; }
+            if rest[0].is_empty() {
+                alternatives.push(letter_id.to_string());
+            } else {
+                let child = ids.pop().expect("Enough IDs for every rule");
+                trie(&rest, child, ids, letters, rules);
+                alternatives.push(format!("{} {}", letter_id, child));
+            }
+        }
+        rules.push(format!("{}: {}", id, alternatives.join(" | ")));
+    }
+    let mut ids: Vec<_> = (1..200).filter(|id| ![8, 11, 31, 42].contains(id)).collect();
+    rng.shuffle(&mut ids);
+    let letters = [ids.pop().expect("Non-empty"), ids.pop().expect("Non-empty")];
+    let mut rules = vec!("0: 8 11".to_string(), "8: 42".to_string(), "11: 42 31".to_string(),
+                         format!("{}: \"a\"", letters[0]), format!("{}: \"b\"", letters[1]));
+    trie(&chunks42.iter().map(|c| c.as_str()).collect::<Vec<_>>(), 42, &mut ids, &letters, &mut rules);
+    trie(&chunks31.iter().map(|c| c.as_str()).collect::<Vec<_>>(), 31, &mut ids, &letters, &mut rules);
+    rng.shuffle(&mut rules);
+
+    // How many 42 chunks and then 31 chunks a message consists of, if it has that shape
+    let shape = |message: &str| -> Option<(usize, usize)> {
+        if message.len() % len != 0 { return None; }
+        let kinds: Vec<_> = (0..message.len() / len).map(|i| chunks42.iter().any(|c| *c == message[i*len..(i+1)*len])).collect();
+        let count42 = kinds.iter().take_while(|&&k| k).count();
+        if kinds[count42..].iter().any(|&k| k) { return None; }
+        Some((count42, kinds.len() - count42))
+    };
+    let (mut part1, mut part2) = (0, 0);
+    let mut messages = Vec::new();
+    while messages.len() < size {
+        let pick = |rng: &mut Rng, chunks: &[String]| chunks[rng.range(0, chunks.len() as u64) as usize].clone();
+        let message = match rng.range(0, 4) {
+            // Valid once 8 and 11 are recursive
+            0 => {
+                let count31 = rng.range(1, 4) as usize;
+                let count42 = count31 + rng.range(1, 5) as usize;
+                let mut message: String = (0..count42).map(|_| pick(rng, &chunks42)).collect();
+                message.extend((0..count31).map(|_| pick(rng, &chunks31)));
+                message
+            },
+            1 => [pick(rng, &chunks42), pick(rng, &chunks42), pick(rng, &chunks31)].concat(),
+            2 => (0..rng.range(1, 8)).map(|_| if rng.chance(1, 2) { pick(rng, &chunks42) } else { pick(rng, &chunks31) }).collect(),
+            _ => (0..rng.range(1, 8 * len as u64)).map(|_| if rng.chance(1, 2) { 'a' } else { 'b' }).collect(),
+        };
+        match shape(&message) {
+            Some((count42, count31)) if count31 >= 1 && count42 > count31 => {
+                part2 += 1;
+                if (count42, count31) == (2, 1) { part1 += 1; }
+            },
+            _ => {},
+        }
+        messages.push(message);
+    }
+    Ok(Generated{ input: format!("{}\n\n{}\n", rules.join("\n"), messages.join("\n")), part1: part1.into(), part2: part2.into() })
+}
+
 fn parse_data(input: &str) -> Result<(Rules, Vec<String>)> {
     let data: Vec<_> = input.split("\n\n").collect();
-    anyhow::ensure!(data.len() == 2);
+    anyhow::ensure!(data.len() == 2, "Expected rules and messages separated by a blank line");
     Ok((data[0].parse()?, data[1].split("\n").map(|s|s.to_string()).collect()))
 }
 
-fn read_data() -> Result<(Rules, Vec<String>)> {
-    parse_data(include_str!("../data/day19.txt").trim())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,11 +420,11 @@ mod tests {
         let (mut rules, texts) = parse_example1();
 
         let expected = vec!("ababbb", "abbbab");
-        assert_eq!(rules.check_all(&texts), expected);
+        assert_eq!(rules.check_all(&texts).unwrap(), expected);
         rules.reduce();
-        assert_eq!(rules.check_all(&texts), expected);
+        assert_eq!(rules.check_all(&texts).unwrap(), expected);
         rules.make_recursive();
-        assert_eq!(rules.check_all(&texts), expected); // no change for example one
+        assert_eq!(rules.check_all(&texts).unwrap(), expected); // no change for example one
     }
 
     #[test]
@@ -299,9 +432,9 @@ mod tests {
         let (mut rules, texts) = parse_example2();
 
         let expected = vec!("bbabbbbaabaabba", "ababaaaaaabaaab", "ababaaaaabbbaba");
-        assert_eq!(rules.check_all(&texts), expected);
+        assert_eq!(rules.check_all(&texts).unwrap(), expected);
         rules.reduce();
-        assert_eq!(rules.check_all(&texts), expected);
+        assert_eq!(rules.check_all(&texts).unwrap(), expected);
 
         rules.make_recursive();
         let expected = vec!(
@@ -317,11 +450,16 @@ mod tests {
             "aaaaabbaabaaaaababaa",
             "aaaabbaabbaaaaaaabbbabbbaaabbaabaaa",
             "aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba");
-        assert_eq!(rules.check_all(&texts), expected);
+        assert_eq!(rules.check_all(&texts).unwrap(), expected);
     }
 
     #[test]
     fn parse_file() {
-        read_data().unwrap();
+        parse_data(include_str!("../data/day19.txt").trim()).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Messages>("generated messages", 20, 1..=100);
     }
 }
@@ -1,34 +1,51 @@
 use std::fmt;
 use std::str::FromStr;
-use anyhow::{Error,Result};
+use anyhow::{bail, ensure, Context, Error, Result};
 use crate::parsing::{regex_captures, capture_group};
 use std::collections::{HashMap, VecDeque, BTreeSet, HashSet};
 use crate::euclid::{Point, point, Vector, vector};
 use crate::console::{Console, Color};
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
 
 const TOP: Vector = vector(0, -1);
 const LEFT: Vector = vector(-1, 0);
 const BOTTOM: Vector = vector(0, 1);
 const RIGHT: Vector = vector(1, 0);
 
-pub fn advent() {
-    let pieces = parse_data();
+impl Solution for Pieces {
+    fn parse(input: &str) -> Result<Self> {
+        Pieces::create(input.trim().split("\n\n").map(|t| t.parse()).collect::<Result<Vec<_>>>()?)
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let mut corners = self.corners();
+        corners.sort_unstable();
+        let product = corners.iter().map(|&i| i as i64).product::<i64>();
+        Console::detail(format!("Corners: {:?} - product: {}", corners, product));
+        Ok(product.into())
+    }
 
-    let corners = pieces.corners();
-    println!("Corners: {:?} - product: {}", corners, corners.iter().map(|&i| i as i64).product::<i64>());
+    fn part2(&self) -> Result<Answer> {
+        let mut image = self.clone().arrange_pieces();
+        Console::colorize_char('░', Color::RED);
+        Console::interactive_display(&image, std::time::Duration::from_millis(500));
 
-    let mut image = pieces.arrange_pieces();
-    Console::colorize_char('░', Color::RED);
-    Console::interactive_display(&image, std::time::Duration::from_millis(500));
+        image.find_sea_monsters().map_err(Failure::no_solution)?;
+        Console::colorize_char('▒', Color::GREEN);
+        Console::interactive_display(&image, std::time::Duration::from_millis(500));
+        Console::clear_interactive();
 
-    image.find_sea_monsters();
-    Console::colorize_char('▒', Color::GREEN);
-    Console::interactive_display(&image, std::time::Duration::from_millis(500));
-    Console::clear_interactive();
+        let remaining = image.pixels.len() - image.monsters.iter().map(|m| m.len()).sum::<usize>();
+        Console::detail(format!("Found {} monsters in {} candidate pixels; {} remain",
+                                image.monsters.len(), image.pixels.len(), remaining));
+        Ok(remaining.into())
+    }
 
-    println!("Found {} monsters in {} candidate pixels; {} remain",
-             image.monsters.len(), image.pixels.len(),
-             image.pixels.len() - image.monsters.iter().map(|m| m.len()).sum::<usize>());
+    /// Generates a `size` by `size` image split into tiles.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_tiles(rng, size)
+    }
 }
 
 fn bits_to_int<'a>(bits: impl Iterator<Item=&'a bool>) -> u32 {
@@ -172,7 +189,7 @@ impl FromStr for Tile {
     type Err = Error;
     fn from_str(str: &str) -> Result<Self> {
         let lines: Vec<_> = str.trim().split("\n").collect();
-        anyhow::ensure!(lines.len() == 11);
+        ensure!(lines.len() == Tile::SIDE_LEN+1, "Expected an ID and {} rows: {}", Tile::SIDE_LEN, str);
         let regex = static_regex!(r"Tile (\d+):");
         let caps = regex_captures(regex, lines[0])?;
         let id = capture_group(&caps, 1).parse()?;
@@ -180,8 +197,8 @@ impl FromStr for Tile {
         let mut grid = [[false; Tile::SIDE_LEN]; Tile::SIDE_LEN];
         for y in 0..Tile::SIDE_LEN {
             for x in 0..Tile::SIDE_LEN {
-                let c = lines[y+1].chars().skip(x).next().unwrap();
-                anyhow::ensure!(c == '#' || c == '.');
+                let c = lines[y+1].chars().nth(x).with_context(|| format!("Tile {} row {} is too short", id, y))?;
+                ensure!(c == '#' || c == '.', "Tile {} has unexpected character {:?}", id, c);
                 grid[x][y] = c == '#';
             }
         }
@@ -189,13 +206,14 @@ impl FromStr for Tile {
     }
 }
 
-struct Pieces {
+#[derive(Clone)]
+pub struct Pieces {
     tiles: HashMap<i32, Tile>,
     neighbors: HashMap<i32, BTreeSet<i32>>,
 }
 
 impl Pieces {
-    fn create(tiles: Vec<Tile>) -> Pieces {
+    fn create(tiles: Vec<Tile>) -> Result<Pieces> {
         let tiles: HashMap<_, _> = tiles.into_iter().map(|t| (t.id, t)).collect();
         let mut edges= HashMap::new();
         for tile in tiles.values() {
@@ -205,7 +223,7 @@ impl Pieces {
         }
         if let Some((edge, ids)) =
                 edges.iter().find(|(_, v)| !(1..=2).contains(&v.len())) {
-            panic!("Unexpected edge counts; edge {:010b} matched tiles {:?}", edge, ids);
+            bail!("Unexpected edge counts; edge {:010b} matched tiles {:?}", edge, ids);
         }
 
         let mut neighbors = HashMap::new();
@@ -218,7 +236,9 @@ impl Pieces {
         // Remove unpaired edges, we don't care about them
         edges.retain(|_, v| v.len() > 1);
 
-        Pieces{tiles, neighbors}
+        let pieces = Pieces{tiles, neighbors};
+        ensure!(pieces.corners().len() == 4, "Expected 4 corner tiles, found {:?}", pieces.corners());
+        Ok(pieces)
     }
 
     fn corners(&self) -> Vec<i32> {
@@ -280,7 +300,7 @@ impl Image {
         Image{pixels, monsters: Vec::new()}
     }
 
-    fn find_sea_monsters(&mut self) {
+    fn find_sea_monsters(&mut self) -> Result<()> {
         // https://math.stackexchange.com/q/1330161/1887
         fn rotate(shape: &[Vector]) -> Vec<Vector> {
             shape.iter().map(|vec| vector(-vec.y, vec.x)).collect()
@@ -311,12 +331,12 @@ impl Image {
                 let found = scan(&self.pixels, &shape);
                 if !found.is_empty() {
                     self.monsters = found;
-                    return;
+                    return Ok(());
                 }
                 shape = rotate(&shape);
             }
         }
-        panic!("No sea monsters found; bad data?")
+        bail!("No sea monsters found")
     }
 }
 
@@ -343,9 +363,109 @@ impl fmt::Display for Image {
     }
 }
 
-fn parse_data() -> Pieces {
-    Pieces::create(include_str!("../data/day20.txt").trim()
-        .split("\n\n").map(|t| t.parse()).collect::<Result<Vec<_>>>().unwrap())
+// Every rotation and reflection of the sea monster, shifted so its top-left corner is at the origin
+fn monster_orientations() -> Vec<Vec<(usize, usize)>> {
+    let mut orientations = Vec::new();
+    for &flip in &[false, true] {
+        let mut shape: Vec<_> = Image::MONSTER.iter().map(|v| if flip { (-v.x, v.y) } else { (v.x, v.y) }).collect();
+        for _ in 0..4 {
+            let (min_x, min_y) = (shape.iter().map(|v| v.0).min().unwrap(), shape.iter().map(|v| v.1).min().unwrap());
+            orientations.push(shape.iter().map(|&(x, y)| ((x - min_x) as usize, (y - min_y) as usize)).collect());
+            shape = shape.iter().map(|&(x, y)| (-y, x)).collect();
+        }
+    }
+    orientations
+}
+
+// Where `shape` appears in `pixels`, as its top-left corners
+fn find_shape(pixels: &[Vec<bool>], shape: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let len = pixels.len();
+    let (width, height) = (shape.iter().map(|v| v.0).max().unwrap() + 1, shape.iter().map(|v| v.1).max().unwrap() + 1);
+    if width > len || height > len { return Vec::new(); }
+    (0..=len - height).flat_map(|y| (0..=len - width).map(move |x| (x, y)))
+        .filter(|&(x, y)| shape.iter().all(|&(dx, dy)| pixels[y + dy][x + dx]))
+        .collect()
+}
+
+// The image is built first, with monsters in a single orientation and noise around them, and then
+// cut into tiles along randomly chosen borders. Every border is distinct, even when flipped, so
+// there's only one way to put the tiles back together.
+fn generate_tiles(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((3..=12).contains(&size), "Size should be between 3 and 12 tiles");
+    let len = size * Tile::IMG_LEN;
+    let orientations = monster_orientations();
+    let (pixels, monsters) = loop {
+        let shape = &orientations[rng.range(0, orientations.len() as u64) as usize];
+        let (width, height) = (shape.iter().map(|v| v.0).max().unwrap() + 1, shape.iter().map(|v| v.1).max().unwrap() + 1);
+        let mut monster = vec!(vec!(false; len); len);
+        let mut monsters = 0;
+        for _ in 0..rng.range(1, (size * size / 4 + 2) as u64) {
+            let (x, y) = (rng.range(0, (len - width + 1) as u64) as usize, rng.range(0, (len - height + 1) as u64) as usize);
+            if shape.iter().any(|&(dx, dy)| monster[y + dy][x + dx]) { continue; }
+            for &(dx, dy) in shape { monster[y + dy][x + dx] = true; }
+            monsters += 1;
+        }
+        let pixels: Vec<Vec<_>> = monster.iter().map(|row| row.iter().map(|&m| m || rng.chance(3, 8)).collect()).collect();
+        // The noise mustn't add more monsters, in this orientation or any other
+        let found: usize = orientations.iter().map(|o| find_shape(&pixels, o).len()).sum();
+        if found == monsters && find_shape(&pixels, shape).len() == monsters { break (pixels, monsters); }
+    };
+
+    // Borders are shared by the tiles on either side, and their ends by the borders meeting there
+    let corners: Vec<Vec<_>> = (0..=size).map(|_| (0..=size).map(|_| rng.chance(1, 2)).collect()).collect();
+    let mut seen = HashSet::new();
+    let mut border = |start: bool, end: bool| -> Result<[bool; Tile::SIDE_LEN]> {
+        for _ in 0..1000 {
+            let mut bits = [start; Tile::SIDE_LEN];
+            for bit in bits.iter_mut().take(Tile::SIDE_LEN - 1).skip(1) { *bit = rng.chance(1, 2); }
+            bits[Tile::SIDE_LEN - 1] = end;
+            let side = bits_to_int(bits.iter());
+            if side != side.reverse_bits() >> (32 - Tile::SIDE_LEN) && seen.insert(normalize(side)) {
+                return Ok(bits);
+            }
+        }
+        bail!("Ran out of distinct borders")
+    };
+    let horizontal = corners.iter()
+        .map(|row| row.windows(2).map(|ends| border(ends[0], ends[1])).collect::<Result<Vec<_>>>())
+        .collect::<Result<Vec<_>>>()?;
+    let vertical = corners.windows(2)
+        .map(|rows| (0..=size).map(|x| border(rows[0][x], rows[1][x])).collect::<Result<Vec<_>>>())
+        .collect::<Result<Vec<_>>>()?;
+
+    let ids = rng.distinct(size * size, 1000, 10000);
+    let mut tiles = Vec::new();
+    for (i, &id) in ids.iter().enumerate() {
+        let (tx, ty) = (i % size, i / size);
+        let mut grid = [[false; Tile::SIDE_LEN]; Tile::SIDE_LEN];
+        for j in 0..Tile::SIDE_LEN {
+            grid[0][j] = horizontal[ty][tx][j];
+            grid[Tile::SIDE_LEN - 1][j] = horizontal[ty + 1][tx][j];
+            grid[j][0] = vertical[ty][tx][j];
+            grid[j][Tile::SIDE_LEN - 1] = vertical[ty][tx + 1][j];
+        }
+        for y in 0..Tile::IMG_LEN {
+            for x in 0..Tile::IMG_LEN {
+                grid[y + 1][x + 1] = pixels[ty * Tile::IMG_LEN + y][tx * Tile::IMG_LEN + x];
+            }
+        }
+        let mut tile = Tile::create(id as i32, grid);
+        for _ in 0..rng.range(0, 4) { tile.rotate(); }
+        if rng.chance(1, 2) { tile.flip_horizontal(); }
+        tiles.push(tile);
+    }
+    rng.shuffle(&mut tiles);
+
+    // Written so that Tile::from_str() reads the grid back unchanged
+    let input: Vec<_> = tiles.iter().map(|tile| {
+        let rows: Vec<String> = (0..Tile::SIDE_LEN)
+            .map(|y| (0..Tile::SIDE_LEN).map(|x| if tile.grid[x][y] { '#' } else { '.' }).collect()).collect();
+        format!("Tile {}:\n{}", tile.id, rows.join("\n"))
+    }).collect();
+    let corner_ids = [ids[0], ids[size - 1], ids[size * (size - 1)], ids[size * size - 1]];
+    let roughness = pixels.iter().flatten().filter(|&&p| p).count() - monsters * Image::MONSTER.len();
+    Ok(Generated{ input: input.join("\n\n") + "\n",
+                  part1: corner_ids.iter().map(|&id| id as i64).product::<i64>().into(), part2: roughness.into() })
 }
 
 #[cfg(test)]
@@ -354,7 +474,7 @@ mod tests {
 
     fn parse_example() -> Pieces {
         Pieces::create(include_str!("../data/day20_example.txt").trim()
-            .split("\n\n").map(|t| t.parse()).collect::<Result<Vec<_>>>().unwrap())
+            .split("\n\n").map(|t| t.parse()).collect::<Result<Vec<_>>>().unwrap()).unwrap()
     }
 
     #[test]
@@ -368,8 +488,13 @@ mod tests {
     #[test]
     fn count_monsters() {
         let mut image = parse_example().arrange_pieces();
-        image.find_sea_monsters();
+        image.find_sea_monsters().unwrap();
         assert_eq!(image.monsters.len(), 2);
         assert_eq!(image.pixels.len() - image.monsters.iter().map(|m| m.len()).sum::<usize>(), 273);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Pieces>("generated tiles", 5, 3..=4);
+    }
+}
@@ -1,19 +1,82 @@
 use std::collections::{HashSet, HashMap, BTreeMap};
 use std::str::FromStr;
-use anyhow::{Error,Result};
+use anyhow::{ensure, Context, Error, Result};
+use crate::budget;
 use crate::parsing::*;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Failure, Solution};
 
-pub fn advent() {
-    let food = parse_data().unwrap();
-    let candidates = associate_allergens(&food);
+pub struct Foods(Vec<Food>);
 
-    let safe_ingredients: HashSet<_> = safe_ingredients(&food, &candidates);
+impl Solution for Foods {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(Foods(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let candidates = associate_allergens(&self.0);
+        let safe_ingredients: HashSet<_> = safe_ingredients(&self.0, &candidates);
+        let usages = self.0.iter()
+            .flat_map(|f| f.ingredients.iter()).filter(|i| safe_ingredients.contains(i)).count();
+        Console::detail(format!("Safe ingredient usages: {}", usages));
+        Ok(usages.into())
+    }
 
-    println!("Safe ingredient usages: {}", food.iter()
-        .flat_map(|f| f.ingredients.iter()).filter(|i| safe_ingredients.contains(i)).count());
+    fn part2(&self) -> Result<Answer> {
+        let dangerous = reduce_candidates(associate_allergens(&self.0)).map_err(Failure::no_solution)?;
+        let dangerous = dangerous.values().map(|s|s.to_string()).collect::<Vec<_>>().join(",");
+        Console::detail(format!("Dangerous ingredients: {}", dangerous));
+        Ok(dangerous.into())
+    }
 
-    let dangerous = reduce_candidates(candidates);
-    println!("Dangerous ingredients: {}", dangerous.values().map(|s|s.to_string()).collect::<Vec<_>>().join(","));
+    /// Generates foods containing `size` allergens.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_foods(rng, size)
+    }
+}
+
+// Allergen i is in dangerous ingredient i. Foods listing only allergen i may also hold the dangerous
+// ingredients before it, but never those after it, so the allergens can be identified in order.
+// Such foods are added until every safe ingredient is ruled out for every allergen.
+fn generate_foods(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=20).contains(&size), "Size should be between 1 and 20 allergens");
+    let mut names: Vec<String> = Vec::new();
+    while names.len() < size * 5 {
+        let name: String = (0..rng.range(4, 8)).map(|_| (b'a' + rng.range(0, 26) as u8) as char).collect();
+        if !names.contains(&name) { names.push(name); }
+    }
+    let safe = names.split_off(size * 2);
+    let (allergens, dangerous) = (&names[..size], &names[size..]);
+    let mut foods: Vec<(Vec<&String>, Vec<usize>)> = Vec::new();
+    for _ in 0..size * 2 {
+        let count = rng.range(1, size as u64 + 1) as usize;
+        let listed = rng.distinct(count, 0, size as u64);
+        let mut ingredients: Vec<_> = listed.iter().map(|&a| &dangerous[a as usize]).collect();
+        let extra: Vec<_> = dangerous.iter().chain(&safe).filter(|i| !ingredients.contains(i) && rng.chance(1, 3)).collect();
+        ingredients.extend(extra);
+        foods.push((ingredients, listed.into_iter().map(|a| a as usize).collect()));
+    }
+    for a in 0..size {
+        let mut possible: Vec<&String> = safe.iter().collect();
+        while !possible.is_empty() {
+            let mut ingredients: Vec<_> = dangerous[..a].iter().filter(|_| rng.chance(1, 2)).collect();
+            ingredients.push(&dangerous[a]);
+            ingredients.extend(safe.iter().filter(|_| rng.chance(1, 2)));
+            possible.retain(|s| ingredients.contains(s));
+            foods.push((ingredients, vec!(a)));
+        }
+    }
+    rng.shuffle(&mut foods);
+    let part1 = foods.iter().flat_map(|(ingredients, _)| ingredients).filter(|i| safe.contains(i)).count();
+    let by_allergen: BTreeMap<_, _> = allergens.iter().zip(dangerous).collect();
+    let part2 = by_allergen.values().map(|d| d.as_str()).collect::<Vec<_>>().join(",");
+    let input = foods.iter_mut().map(|(ingredients, listed)| {
+        rng.shuffle(ingredients);
+        let listed = listed.iter().map(|&a| allergens[a].as_str()).collect::<Vec<_>>().join(", ");
+        format!("{} (contains {})", ingredients.iter().map(|i| i.as_str()).collect::<Vec<_>>().join(" "), listed)
+    }).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: part2.into() })
 }
 
 fn associate_allergens(food: &[Food]) -> HashMap<String, HashSet<String>> {
@@ -37,10 +100,12 @@ fn safe_ingredients<'a>(food: &'a [Food], candidates: &'a HashMap<String, HashSe
     ret
 }
 
-fn reduce_candidates(mut candidates: HashMap<String, HashSet<String>>) -> BTreeMap<String, String> {
+fn reduce_candidates(mut candidates: HashMap<String, HashSet<String>>) -> Result<BTreeMap<String, String>> {
     let mut ret = BTreeMap::new();
     while !candidates.is_empty() {
+        budget::check().with_context(|| format!("Identified {} allergens, {} remain", ret.len(), candidates.len()))?;
         let singletons: HashMap<_,_> = candidates.iter().filter(|(_, v)| v.len() == 1).collect();
+        ensure!(!singletons.is_empty(), "Allergens are ambiguous: {:?}", candidates);
         let to_remove: HashSet<_> =
             singletons.values().flat_map(|v| v.iter().map(|s|s.to_string())).collect();
 
@@ -50,7 +115,7 @@ fn reduce_candidates(mut candidates: HashMap<String, HashSet<String>>) -> BTreeM
         candidates.values_mut().for_each(|v| v.retain(|i|!to_remove.contains(i)));
         candidates = candidates.into_iter().filter(|(_, v)| !v.is_empty()).collect();
     }
-    ret
+    Ok(ret)
 }
 
 struct Food {
@@ -69,8 +134,8 @@ impl FromStr for Food {
     }
 }
 
-fn parse_data() -> Result<Vec<Food>> {
-    include_str!("../data/day21.txt").trim().split("\n").map(|s|s.parse()).collect::<Result<Vec<_>>>()
+fn parse_data(input: &str) -> Result<Vec<Food>> {
+    input.trim().split("\n").map(|s|s.parse()).collect::<Result<Vec<_>>>()
 }
 
 #[cfg(test)]
@@ -97,7 +162,7 @@ mod tests {
         let expected = vec!("trh", "sbzzf", "kfcds", "nhms").iter().map(|&s|s.to_string()).collect();
         assert_eq!(safe, expected);
 
-        let dangerous = reduce_candidates(candidates);
+        let dangerous = reduce_candidates(candidates).unwrap();
         let expected = vec!(("dairy", "mxmxvkd"), ("fish", "sqjhc"), ("soy", "fvjkl")).iter()
             .map(|(k, v)| (k.to_string(), v.to_string())).collect();
         assert_eq!(dangerous, expected);
@@ -105,6 +170,11 @@ mod tests {
 
     #[test]
     fn parse_file() {
-        parse_data().unwrap();
+        parse_data(include_str!("../data/day21.txt")).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Foods>("generated foods", 20, 1..=10);
     }
 }
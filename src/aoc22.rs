@@ -1,23 +1,97 @@
-use std::collections::{VecDeque, HashSet};
-
-pub fn advent() {
-    //let data = parse_data();
-    let player1: VecDeque<usize> = vec!(
-        29, 25, 9, 1, 17, 28, 12, 49, 8, 15, 41, 31, 39, 24, 40, 23, 6, 21, 13, 45, 20, 2, 42, 47, 10
-    ).into_iter().collect();
-    let player2: VecDeque<usize> = vec!(
-        46, 27, 44, 18, 30, 50, 37, 11, 43, 35, 34, 4, 22, 7, 33, 16, 36, 26, 48, 19, 38, 14, 5, 3, 32
-    ).into_iter().collect();
-
-    let (result1, result2) = play_game(player1.clone(), player2.clone());
-    println!("Player 1's deck: {:?}\nPlayer 2's deck: {:?}", result1, result2);
-    let winner = if result2.is_empty() { result1 } else { result2 };
-    println!("Score: {}\n", score(&winner));
+use std::collections::{BTreeSet, VecDeque, HashSet};
+use anyhow::{ensure, Context, Result};
+use crate::budget;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
+
+pub struct Combat {
+    player1: VecDeque<usize>,
+    player2: VecDeque<usize>,
+}
+
+impl Solution for Combat {
+    fn parse(input: &str) -> Result<Self> {
+        fn parse_deck(deck: &str) -> Result<VecDeque<usize>> {
+            deck.lines().skip(1).map(|c| c.parse().context(c.to_string())).collect()
+        }
+        let decks: Vec<_> = input.trim().split("\n\n").collect();
+        anyhow::ensure!(decks.len() == 2, "Expected two decks");
+        let (player1, player2) = (parse_deck(decks[0])?, parse_deck(decks[1])?);
+        // Hands can't be tied
+        let distinct: HashSet<_> = player1.iter().chain(player2.iter()).collect();
+        anyhow::ensure!(distinct.len() == player1.len() + player2.len(), "Cards must be distinct");
+        Ok(Combat{ player1, player2 })
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let (result1, result2) = play_game(self.player1.clone(), self.player2.clone());
+        Ok(describe_result(result1, result2).into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let (result1, result2) = play_recursive_game(self.player1.clone(), self.player2.clone())?;
+        Ok(describe_result(result1, result2).into())
+    }
 
-    let (result1, result2) = play_recursive_game(player1.clone(), player2.clone());
-    println!("Player 1's deck: {:?}\nPlayer 2's deck: {:?}", result1, result2);
+    /// Generates a game with `size` cards per player.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_decks(rng, size)
+    }
+}
+
+// Deals a shuffled deck, and plays both games with plain Vecs rather than play_game() and
+// play_recursive_game(). Deals that repeat in a regular game, and so never end, are redrawn.
+fn generate_decks(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=25).contains(&size), "Size should be between 1 and 25 cards per player");
+    let (deal, part1) = loop {
+        let mut deck: Vec<usize> = (1..=size * 2).collect();
+        rng.shuffle(&mut deck);
+        let (mut player1, mut player2) = (deck[..size].to_vec(), deck[size..].to_vec());
+        let mut seen = BTreeSet::new();
+        while !player1.is_empty() && !player2.is_empty() && seen.insert((player1.clone(), player2.clone())) {
+            let (card1, card2) = (player1.remove(0), player2.remove(0));
+            if card1 > card2 { player1.extend(vec!(card1, card2)); } else { player2.extend(vec!(card2, card1)); }
+        }
+        if player1.is_empty() || player2.is_empty() {
+            break (deck, vec_score(&player1) + vec_score(&player2));
+        }
+    };
+    let (mut player1, mut player2) = (deal[..size].to_vec(), deal[size..].to_vec());
+    let part2 = if recursive_winner(&mut player1, &mut player2) { vec_score(&player1) } else { vec_score(&player2) };
+    let deck = |cards: &[usize]| cards.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("\n");
+    let input = format!("Player 1:\n{}\n\nPlayer 2:\n{}\n", deck(&deal[..size]), deck(&deal[size..]));
+    Ok(Generated{ input, part1: part1.into(), part2: part2.into() })
+}
+
+fn vec_score(deck: &[usize]) -> usize {
+    deck.iter().zip((1..=deck.len()).rev()).map(|(c, i)| c * i).sum()
+}
+
+// Plays out a recursive game, and returns whether player 1 won
+fn recursive_winner(player1: &mut Vec<usize>, player2: &mut Vec<usize>) -> bool {
+    let mut seen = BTreeSet::new();
+    while !player1.is_empty() && !player2.is_empty() {
+        if !seen.insert((player1.clone(), player2.clone())) { return true; }
+        let (card1, card2) = (player1.remove(0), player2.remove(0));
+        let player1_wins = if card1 <= player1.len() && card2 <= player2.len() {
+            recursive_winner(&mut player1[..card1].to_vec(), &mut player2[..card2].to_vec())
+        } else {
+            card1 > card2
+        };
+        if player1_wins { player1.extend(vec!(card1, card2)); } else { player2.extend(vec!(card2, card1)); }
+    }
+    player2.is_empty()
+}
+
+// Records both final decks, and returns the winner's score
+fn describe_result(result1: VecDeque<usize>, result2: VecDeque<usize>) -> usize {
+    Console::detail(format!("Player 1's deck: {:?}", result1));
+    Console::detail(format!("Player 2's deck: {:?}", result2));
     let winner = if result2.is_empty() { result1 } else { result2 };
-    println!("Score: {}", score(&winner));
+    let score = score(&winner);
+    Console::detail(format!("Score: {}", score));
+    score
 }
 
 fn score(deck: &VecDeque<usize>) -> usize {
@@ -43,19 +117,21 @@ fn play_hand(player1: &mut VecDeque<usize>, player2: &mut VecDeque<usize>) {
     } else { panic!() }
 }
 
-fn play_recursive_game(mut player1: VecDeque<usize>, mut player2: VecDeque<usize>) -> (VecDeque<usize>, VecDeque<usize>) {
+fn play_recursive_game(mut player1: VecDeque<usize>, mut player2: VecDeque<usize>) -> Result<(VecDeque<usize>, VecDeque<usize>)> {
     let mut seen_states: HashSet<(Vec<usize>, Vec<usize>)> = HashSet::new();
     while !player1.is_empty() && !player2.is_empty() {
+        budget::check().with_context(|| format!("Played {} rounds of a game with {} cards",
+                                                seen_states.len(), player1.len() + player2.len()))?;
         if !seen_states.insert((player1.iter().copied().collect(), player2.iter().copied().collect())) {
             // player 1 wins if we see a game state again
-            return (player1, VecDeque::new());
+            return Ok((player1, VecDeque::new()));
         }
-        play_recursive_hand(&mut player1, &mut player2);
+        play_recursive_hand(&mut player1, &mut player2)?;
     }
-    (player1, player2)
+    Ok((player1, player2))
 }
 
-fn play_recursive_hand(player1: &mut VecDeque<usize>, player2: &mut VecDeque<usize>) {
+fn play_recursive_hand(player1: &mut VecDeque<usize>, player2: &mut VecDeque<usize>) -> Result<()> {
     //println!("---{}---\nPlayer 1's deck: {:?}\nPlayer 2's deck: {:?}\n---", player1.len()+player2.len(), player1, player2);
     let card1 = player1.pop_front().unwrap();
     let card2 = player2.pop_front().unwrap();
@@ -63,7 +139,7 @@ fn play_recursive_hand(player1: &mut VecDeque<usize>, player2: &mut VecDeque<usi
         let (result1, result2) =
             play_recursive_game(
                 player1.iter().take(card1).copied().collect(),
-                player2.iter().take(card2).copied().collect());
+                player2.iter().take(card2).copied().collect())?;
         if result2.is_empty() {
             player1.push_back(card1);
             player1.push_back(card2);
@@ -80,6 +156,7 @@ fn play_recursive_hand(player1: &mut VecDeque<usize>, player2: &mut VecDeque<usi
             player2.push_back(card1);
         } else { panic!() }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -100,7 +177,7 @@ mod tests {
     fn recursive_combat() {
         let player1: VecDeque<usize> = vec!(9,2,6,3,1).into_iter().collect();
         let player2: VecDeque<usize> = vec!(5,8,4,7,10).into_iter().collect();
-        let (result1, result2) = play_recursive_game(player1, player2);
+        let (result1, result2) = play_recursive_game(player1, player2).unwrap();
         assert!(result1.is_empty());
         assert_eq!(result2, [7, 5, 6, 2, 4, 1, 10, 8, 9, 3]);
         assert_eq!(score(&result2), 291);
@@ -113,6 +190,11 @@ mod tests {
 
         // Problem statement doesn't actually provide results for this example (and it's not really
         // interesting, player1 wins by default essentially) we really just care that it terminates.
-        play_recursive_game(player1, player2);
+        play_recursive_game(player1, player2).unwrap();
+    }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Combat>("generated decks", 20, 1..=10);
     }
 }
@@ -1,25 +1,116 @@
 use std::fmt;
+use anyhow::{ensure, Context, Result};
+use crate::budget;
+use crate::options::{Kind, Opt, Options};
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
+
+pub struct CupGame {
+    cups: Vec<usize>,
+    rounds: usize,
+    long_rounds: usize,
+}
 
-pub fn advent() {
-    let input_u = vec!(4,6,7,5,2,8,1,9,3);
+impl Solution for CupGame {
+    fn parse(input: &str) -> Result<Self> {
+        let cups = input.trim().chars()
+            .map(|c| c.to_digit(10).map(|d| d as usize).with_context(|| format!("Invalid cup: {}", c)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut labels = cups.clone();
+        labels.sort_unstable();
+        ensure!(cups.len() >= 5 && labels == (1..=cups.len()).collect::<Vec<_>>(),
+                "Cups must be labeled 1 through N, for some N >= 5: {}", input.trim());
+        Ok(CupGame{ cups, rounds: 100, long_rounds: 10000000 })
+    }
 
-    let mut cups = Cups::create(&input_u);
-    elapsed! {
-        for _ in 0..100 {
-            cups.play_round();
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "rounds", kind: Kind::Number{ min: 0 }, default: Some("100"),
+               help: "How many rounds to play in part 1" },
+          Opt{ name: "long-rounds", kind: Kind::Number{ min: 0 }, default: Some("10000000"),
+               help: "How many rounds to play with a million cups in part 2" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(rounds) = options.number("rounds") {
+            self.rounds = rounds;
+        }
+        if let Some(rounds) = options.number("long-rounds") {
+            self.long_rounds = rounds;
         }
+        Ok(())
     }
-    println!("After 100 rounds: {}",
-             cups.iter_from1().skip(1).map(|v| format!("{}", v)).collect::<Vec<_>>().join(""));
 
-    let mut cups = Cups::create(&input_u.iter().copied().chain(10..=1000000).collect::<Vec<_>>());
-    elapsed! {
-        for _ in 0..10000000 {
-            cups.play_round();
+    fn part1(&self) -> Result<Answer> {
+        let mut cups = Cups::create(&self.cups);
+        span!("Play rounds", cups.play_rounds(self.rounds))?;
+        let labels = cups.iter_from1().skip(1).map(|v| format!("{}", v)).collect::<Vec<_>>().join("");
+        Console::detail(format!("After {} rounds: {}", self.rounds, labels));
+        Ok(labels.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let mut cups = Cups::create(&self.cups.iter().copied().chain(self.cups.len()+1..=1000000).collect::<Vec<_>>());
+        span!("Play long rounds", cups.play_rounds(self.long_rounds))?;
+        let product = cups.iter_from1().skip(1).take(2).map(|v| v as u64).product::<u64>();
+        Console::detail(format!("After CRAB rounds; cups product: {}", product));
+        Ok(product.into())
+    }
+
+    /// Generates a circle of `size` cups.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_cups(rng, size)
+    }
+}
+
+// Shuffles the cups, and plays the default rounds independently of Cups: part 1 moves cups around a
+// Vec with the current cup at the front, and part 2 follows a table of each cup's clockwise neighbor.
+fn generate_cups(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((5..=9).contains(&size), "Size should be between 5 and 9 cups");
+    let mut cups: Vec<usize> = (1..=size).collect();
+    rng.shuffle(&mut cups);
+    let input: String = cups.iter().map(|c| c.to_string()).collect();
+
+    let mut circle = cups.clone();
+    for _ in 0..100 {
+        let picked: Vec<_> = circle.drain(1..4).collect();
+        let mut dest = circle[0];
+        loop {
+            dest = if dest == 1 { size } else { dest - 1 };
+            if !picked.contains(&dest) { break; }
+        }
+        let at = circle.iter().position(|&c| c == dest).unwrap() + 1;
+        circle.splice(at..at, picked);
+        circle.rotate_left(1);
+    }
+    let one = circle.iter().position(|&c| c == 1).unwrap();
+    circle.rotate_left(one);
+    let part1: String = circle[1..].iter().map(|c| c.to_string()).collect();
+
+    const CUPS: u32 = 1000000;
+    let labels: Vec<u32> = cups.iter().map(|&c| c as u32).chain(size as u32 + 1..=CUPS).collect();
+    let mut clockwise = vec![0; CUPS as usize + 1];
+    for (i, &label) in labels.iter().enumerate() {
+        clockwise[label as usize] = labels[(i + 1) % labels.len()];
+    }
+    let mut current = labels[0];
+    for _ in 0..10000000 {
+        let a = clockwise[current as usize];
+        let b = clockwise[a as usize];
+        let c = clockwise[b as usize];
+        clockwise[current as usize] = clockwise[c as usize];
+        let mut dest = current;
+        loop {
+            dest = if dest == 1 { CUPS } else { dest - 1 };
+            if dest != a && dest != b && dest != c { break; }
         }
+        clockwise[c as usize] = clockwise[dest as usize];
+        clockwise[dest as usize] = a;
+        current = clockwise[current as usize];
     }
-    println!("After CRAB rounds; cups product: {}",
-             cups.iter_from1().skip(1).take(2).map(|v| v as u64).product::<u64>());
+    let after1 = clockwise[1];
+    let part2 = after1 as u64 * clockwise[after1 as usize] as u64;
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: part2.into() })
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +143,16 @@ impl Cups {
         CupsIterator{ cups: self, ptr: Some(1), start: 1 }
     }
 
+    fn play_rounds(&mut self, rounds: usize) -> Result<()> {
+        for round in 0..rounds {
+            if round % 1024 == 0 {
+                budget::check().with_context(|| format!("Played {} of {} rounds", round, rounds))?;
+            }
+            self.play_round();
+        }
+        Ok(())
+    }
+
     fn play_round(&mut self) {
         let to_move: Vec<_> = self.iter().skip(1).take(3).collect();
         self.next[self.head] = self.next[to_move[2]]; // "remove" [1..3]
@@ -122,4 +223,10 @@ mod tests {
         let result = cups.iter_from1().take(3).collect::<Vec<_>>();
         assert_eq!(result, vec!(1, 934001, 159792));
     }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore)] // too slow to run without --release
+    fn generated() {
+        crate::differential::check_generator::<CupGame>("generated cups", 3, 5..=9);
+    }
 }
@@ -1,16 +1,94 @@
 use std::cmp::min;
-use std::collections::{HashMap, HashSet};
-use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use anyhow::{ensure, Context, Result};
+use crate::budget;
+use crate::console::{Color, Console};
+use crate::euclid::{point, Point};
+use crate::generate::{Generated, Rng};
+use crate::options::{Kind, Opt, Options};
+use crate::solution::{Answer, Solution};
 
-pub fn advent() {
-    let moves = read_data().unwrap();
-    let mut tiles = identify_tiles(&moves);
-    println!("Initial Black Tiles: {}", tiles.len());
+pub struct Lobby {
+    moves: Vec<Vec<Move>>,
+    days: usize,
+}
+
+impl Solution for Lobby {
+    fn parse(input: &str) -> Result<Self> {
+        // Black tiles are left as #, which stands out against the solid white tiles
+        Console::colorize_char('.', Color::GREY);
+        Ok(Lobby{ moves: read_data(input)?, days: 100 })
+    }
+
+    fn options() -> &'static [Opt] {
+        &[Opt{ name: "days", kind: Kind::Number{ min: 0 }, default: Some("100"),
+               help: "How many days of tile flipping to simulate in part 2" }]
+    }
+
+    fn configure(&mut self, options: &Options) -> Result<()> {
+        if let Some(days) = options.number("days") {
+            self.days = days;
+        }
+        Ok(())
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let black = identify_tiles(&self.moves).len();
+        Console::detail(format!("Initial Black Tiles: {}", black));
+        Ok(black.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        let mut tiles = identify_tiles(&self.moves);
+        for day in 0..self.days {
+            budget::check()
+                .with_context(|| format!("Simulated {} of {} days; {} tiles are black", day, self.days, tiles.len()))?;
+            tiles = day_passes(&tiles);
+            Console::interactive_display(HexFloor(&tiles), std::time::Duration::from_millis(50));
+        }
+        Console::clear_interactive();
+        Console::detail(format!("Black Tiles after {} days: {}", self.days, tiles.len()));
+        Ok(tiles.len().into())
+    }
 
+    /// Generates `size` paths.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_paths(rng, size)
+    }
+}
+
+// Some paths are reorderings of earlier ones, so they flip the same tile back. Tiles are tracked in
+// axial (q, r) coordinates rather than as HexPoints, over the default 100 days.
+fn generate_paths(rng: &mut Rng, size: usize) -> Result<Generated> {
+    const STEPS: [(&str, i32, i32); 6] = [("e", 1, 0), ("w", -1, 0), ("ne", 1, -1), ("nw", 0, -1), ("se", 0, 1), ("sw", -1, 1)];
+    ensure!((1..=500).contains(&size), "Size should be between 1 and 500 paths");
+    let mut paths: Vec<Vec<usize>> = Vec::with_capacity(size);
+    let mut flips = BTreeMap::new();
+    for _ in 0..size {
+        let path = if !paths.is_empty() && rng.chance(1, 5) {
+            let mut path = paths[rng.range(0, paths.len() as u64) as usize].clone();
+            rng.shuffle(&mut path);
+            path
+        } else {
+            (0..rng.range(1, 20)).map(|_| rng.range(0, 6) as usize).collect()
+        };
+        let tile = path.iter().fold((0, 0), |(q, r), &s| (q + STEPS[s].1, r + STEPS[s].2));
+        *flips.entry(tile).or_insert(0) += 1;
+        paths.push(path);
+    }
+    let mut black: BTreeSet<(i32, i32)> = flips.into_iter().filter(|&(_, f)| f % 2 == 1).map(|(t, _)| t).collect();
+    let part1 = black.len();
+    let neighbors = |(q, r): (i32, i32)| STEPS.iter().map(move |&(_, dq, dr)| (q + dq, r + dr));
     for _ in 0..100 {
-        tiles = day_passes(&tiles);
+        let candidates: BTreeSet<_> = black.iter().flat_map(|&t| neighbors(t).chain(Some(t))).collect();
+        black = candidates.into_iter().filter(|&t| {
+            let count = neighbors(t).filter(|n| black.contains(n)).count();
+            count == 2 || (count == 1 && black.contains(&t))
+        }).collect();
     }
-    println!("Black Tiles after 100 days: {}", tiles.len());
+    let input = paths.iter().map(|p| p.iter().map(|&s| STEPS[s].0).collect::<String>()).collect::<Vec<_>>().join("\n");
+    Ok(Generated{ input: input + "\n", part1: part1.into(), part2: black.len().into() })
 }
 
 fn identify_tiles(moves: &Vec<Vec<Move>>) -> HashSet<HexPoint> {
@@ -45,6 +123,27 @@ fn day_passes(black_tiles: &HashSet<HexPoint>) -> HashSet<HexPoint> {
     ret
 }
 
+// Renders black tiles as # and white tiles as ., offsetting alternate rows by half a tile
+struct HexFloor<'a>(&'a HashSet<HexPoint>);
+
+impl fmt::Display for HexFloor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let black: HashSet<_> = self.0.iter().map(|t| t.to_point()).collect();
+        let mut out = String::new();
+        let mut last_y = None;
+        for pos in Point::display_order_box(black.iter().cloned()).into_iter().flatten() {
+            if let Some(last_y) = last_y {
+                if pos.y != last_y { out.push('\n'); }
+            }
+            last_y = Some(pos.y);
+            // Only every other column holds a tile
+            let c = if (pos.x + pos.y) % 2 != 0 { ' ' } else if black.contains(&pos) { '#' } else { '.' };
+            out.push(c);
+        }
+        write!(f, "{}", out.trim_end())
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 enum Move {
     EAST,
@@ -144,6 +243,11 @@ impl HexPoint {
         pos
     }
 
+    // "Doubled" coordinates: east is two columns over, the diagonals one column and one row
+    fn to_point(self) -> Point {
+        point(2*self.run + self.run_up + self.run_down, self.run_down - self.run_up)
+    }
+
     fn adjacent(&self) -> Vec<HexPoint> {
         vec!(
             HexPoint::create(self.run+1, self.run_up, self.run_down),
@@ -156,8 +260,13 @@ impl HexPoint {
     }
 }
 
-fn read_data() -> Result<Vec<Vec<Move>>> {
-    include_str!("../data/day24.txt").trim().split("\n").map(|s| to_moves(s)).collect()
+fn read_data(input: &str) -> Result<Vec<Vec<Move>>> {
+    input.trim().split("\n").enumerate()
+        .map(|(i, s)| {
+            ensure!(!s.is_empty(), "Line {} is empty", i + 1);
+            to_moves(s).with_context(|| format!("Line {}", i + 1))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -178,6 +287,15 @@ mod tests {
         b: ("nwwswee", HexPoint::create(0, 0, 0)),
     }
 
+    parameterized_test::create!{ bad_paths, input, {
+        assert!(read_data(input).is_err());
+        }}
+    bad_paths! {
+        empty: "",
+        blank_line: "esew\n\nnwwswee",
+        bad_move: "esx",
+    }
+
     #[test]
     fn example() {
         let mut tiles = identify_tiles(&read_example().unwrap());
@@ -196,8 +314,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn display() {
+        let tiles = vec!(HexPoint::create(0, 0, 0), HexPoint::create(2, 0, 0), HexPoint::create(0, 0, 1)).into_iter().collect();
+        assert_eq!(HexFloor(&tiles).to_string(), "# . #\n # .");
+    }
+
     #[test]
     fn parse_file() {
-        read_data().unwrap();
+        read_data(include_str!("../data/day24.txt")).unwrap();
+    }
+
+    // Each case simulates 100 days three times, so only one is checked
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Lobby>("generated paths", 1, 1..=30);
     }
 }
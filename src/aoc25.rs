@@ -1,21 +1,72 @@
-pub fn advent() {
-    let (card_key, door_key) = (3248366, 4738476);
-    let card_loop = find_loop_size(card_key);
-    let door_loop = find_loop_size(door_key);
-    println!("Card Loop: {}, Door Loop: {}", card_loop, door_loop);
-    let key = generate_key(card_loop, door_key);
-    assert_eq!(key, generate_key(door_loop, card_key));
-    println!("Encryption Key: {}", key);
+use anyhow::{ensure, Context, Result};
+use crate::budget;
+use crate::console::Console;
+use crate::generate::{Generated, Rng};
+use crate::solution::{Answer, Solution};
 
+pub struct Handshake {
+    card_key: i64,
+    door_key: i64,
 }
 
-fn find_loop_size(public_key: i64) -> i64 {
+impl Solution for Handshake {
+    fn parse(input: &str) -> Result<Self> {
+        let keys = input.trim().lines().map(|k| k.parse()).collect::<Result<Vec<i64>, _>>()?;
+        ensure!(keys.len() == 2, "Expected card and door public keys");
+        // Every key in this range is reachable, so find_loop_size() terminates
+        ensure!(keys.iter().all(|k| (1..20201227).contains(k)), "Keys must be between 1 and 20201226: {:?}", keys);
+        Ok(Handshake{ card_key: keys[0], door_key: keys[1] })
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        let card_loop = find_loop_size(self.card_key)?;
+        let door_loop = find_loop_size(self.door_key)?;
+        Console::detail(format!("Card Loop: {}, Door Loop: {}", card_loop, door_loop));
+        let key = generate_key(card_loop, self.door_key);
+        ensure!(key == generate_key(door_loop, self.card_key), "Card and door disagree on the key");
+        Console::detail(format!("Encryption Key: {}", key));
+        Ok(key.into())
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        Ok(Answer::None)
+    }
+
+    /// Generates keys with loop sizes below `size` thousand.
+    fn generate(rng: &mut Rng, size: usize) -> Result<Generated> {
+        generate_keys(rng, size)
+    }
+}
+
+// Picks both loop sizes, and derives the keys by squaring and multiplying rather than one loop at a
+// time.
+fn generate_keys(rng: &mut Rng, size: usize) -> Result<Generated> {
+    ensure!((1..=20000).contains(&size), "Size should be between 1 and 20000 thousand loops");
+    fn transform(subject: u64, loop_size: u64) -> u64 {
+        let (mut result, mut base, mut exponent) = (1, subject, loop_size);
+        while exponent > 0 {
+            if exponent & 1 == 1 { result = result * base % 20201227; }
+            base = base * base % 20201227;
+            exponent >>= 1;
+        }
+        result
+    }
+    let (card_loop, door_loop) = (rng.range(1, size as u64 * 1000), rng.range(1, size as u64 * 1000));
+    let (card_key, door_key) = (transform(7, card_loop), transform(7, door_loop));
+    let key = transform(card_key, door_loop);
+    Ok(Generated{ input: format!("{}\n{}\n", card_key, door_key), part1: key.into(), part2: Answer::None })
+}
+
+fn find_loop_size(public_key: i64) -> Result<i64> {
     let subject = 7;
     let mut result = 1;
     for i in 1.. {
         result = (subject * result) % 20201227;
         if result == public_key {
-            return i;
+            return Ok(i);
+        }
+        if i % (1 << 16) == 0 {
+            budget::check().with_context(|| format!("Tried {} loop sizes for key {}", i, public_key))?;
         }
     }
     unreachable!();
@@ -36,11 +87,16 @@ mod tests {
     #[test]
     fn example() {
         let (card_key, door_key) = (5764801, 17807724);
-        let card_loop = find_loop_size(card_key);
-        let door_loop = find_loop_size(door_key);
+        let card_loop = find_loop_size(card_key).unwrap();
+        let door_loop = find_loop_size(door_key).unwrap();
         assert_eq!(card_loop, 8);
         assert_eq!(door_loop, 11);
         assert_eq!(generate_key(card_loop, door_key), 14897079);
         assert_eq!(generate_key(door_loop, card_key), 14897079);
     }
+
+    #[test]
+    fn generated() {
+        crate::differential::check_generator::<Handshake>("generated keys", 20, 1..=100);
+    }
 }
@@ -0,0 +1,89 @@
+use crate::runner::{DayReport, Part};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+pub const HEADER: &str = "file,part1,part2,time_ms,cached,error";
+
+/// The files in `dir` to use as inputs, in name order. Hidden files are skipped.
+pub fn inputs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && !entry.file_name().to_string_lossy().starts_with('.') {
+            inputs.push(entry.path());
+        }
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// One CSV row for the input `file`: its answers, how long the two parts took in total, whether
+/// either answer came from the answer cache, and any errors. Cached parts aren't timed, so they're
+/// left out of the total, which is blank if neither part ran. An input that failed to load leaves
+/// everything but the error blank.
+pub fn row(file: &str, result: &Result<DayReport>) -> String {
+    let cells = match result {
+        Ok(report) => {
+            let answer = |part: &Part| part.answer.as_ref().map(|a| a.to_string()).unwrap_or_default();
+            let errors: Vec<_> = vec!((1, &report.part1), (2, &report.part2)).into_iter()
+                .filter_map(|(n, part)| part.answer.as_ref().err().map(|e| format!("part {}: {:#}", n, e)))
+                .collect();
+            let ran: Vec<_> = vec!(&report.part1, &report.part2).into_iter().filter(|p| !p.cached).collect();
+            let elapsed = if ran.is_empty() { String::new() } else {
+                format!("{:.3}", ran.iter().map(|p| p.elapsed).sum::<std::time::Duration>().as_secs_f64() * 1000.0)
+            };
+            [file.to_string(), answer(&report.part1), answer(&report.part2), elapsed,
+             (ran.len() < 2).to_string(), errors.join("; ")]
+        },
+        Err(e) => [file.to_string(), String::new(), String::new(), String::new(), String::new(), format!("{:#}", e)],
+    };
+    cells.iter().map(|c| escape(c)).collect::<Vec<_>>().join(",")
+}
+
+// Quotes cells containing separators, quotes or line breaks, doubling any quotes (RFC 4180)
+fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Answer;
+    use std::time::Duration;
+
+    #[test]
+    fn rows() {
+        let part = |answer: Result<Answer>, millis| Part{ answer, elapsed: Duration::from_millis(millis), memory: None, cached: false, details: Vec::new() };
+        let solved = Ok(DayReport{ part1: part(Ok(12.into()), 2), part2: part(Ok("a,b".into()), 1) });
+        assert_eq!(row("alice.txt", &solved), r#"alice.txt,12,"a,b",3.000,false,"#);
+
+        let cached = |answer: Answer| Part{ cached: true, ..part(Ok(answer), 0) };
+        let half_cached = Ok(DayReport{ part1: cached(12.into()), part2: part(Ok(5.into()), 1) });
+        assert_eq!(row("alice.txt", &half_cached), "alice.txt,12,5,1.000,true,");
+        let cached = Ok(DayReport{ part1: cached(12.into()), part2: cached(5.into()) });
+        assert_eq!(row("alice.txt", &cached), "alice.txt,12,5,,true,");
+
+        let unsolved = Ok(DayReport{
+            part1: part(Ok(12.into()), 2), part2: part(Err(anyhow::anyhow!("No \"valid\" programs")), 1) });
+        assert_eq!(row("bob.txt", &unsolved), r#"bob.txt,12,,3.000,false,"part 2: No ""valid"" programs""#);
+
+        let unloaded = Err(anyhow::anyhow!("Invalid line").context("Failed to parse day 8"));
+        assert_eq!(row("carol.txt", &unloaded), "carol.txt,,,,,Failed to parse day 8: Invalid line");
+    }
+
+    #[test]
+    fn input_files() {
+        let dir = std::env::temp_dir().join(format!("advent-2020-batch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        for file in &["bob.txt", "alice", ".hidden"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(inputs(&dir).unwrap(), vec!(dir.join("alice"), dir.join("bob.txt")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
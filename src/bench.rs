@@ -0,0 +1,181 @@
+use crate::solution::Day;
+use anyhow::{Context, Error, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+pub const PHASES: [&str; 3] = ["parse", "part1", "part2"];
+
+/// Summary statistics of repeated measurements.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Stats {
+        assert!(!samples.is_empty(), "No samples");
+        samples.sort();
+        // nearest-rank percentiles
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Stats{ min: samples[0], median: percentile(50), p95: percentile(95), max: samples[samples.len()-1] }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "min {:>9.1?}  median {:>9.1?}  p95 {:>9.1?}  max {:>9.1?}",
+               self.min, self.median, self.p95, self.max)
+    }
+}
+
+fn measure(warmup: usize, runs: usize, mut f: impl FnMut() -> Result<()>) -> Result<Stats> {
+    for _ in 0..warmup {
+        f()?;
+    }
+    let mut samples = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f()?;
+        samples.push(start.elapsed());
+    }
+    Ok(Stats::from_samples(samples))
+}
+
+/// Parses the input and solves each part `runs` times, after `warmup` unmeasured runs of each.
+pub fn bench_day(day: &Day, input: &str, args: &[String], warmup: usize, runs: usize) -> Result<[Stats; 3]> {
+    anyhow::ensure!(runs > 0, "Must run at least once");
+    let parse = measure(warmup, runs, || day.load(input, args).map(|_| ()))?;
+    let solution = day.load(input, args)?;
+    let part1 = measure(warmup, runs, || solution.part1().map(|_| ()))?;
+    let part2 = measure(warmup, runs, || solution.part2().map(|_| ()))?;
+    Ok([parse, part1, part2])
+}
+
+/// Previously-recorded timings, keyed by day and phase.
+///
+/// Each line is `DAY PHASE MIN MEDIAN P95 MAX`, with times in nanoseconds.
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<(u32, String), Stats>);
+
+impl Baseline {
+    /// Loads a baseline, or an empty one if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Baseline> {
+        if !path.exists() {
+            return Ok(Baseline::default());
+        }
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?
+            .parse()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    pub fn get(&self, day: u32, phase: &str) -> Option<&Stats> {
+        self.0.get(&(day, phase.to_string()))
+    }
+
+    pub fn record(&mut self, day: u32, phase: &str, stats: Stats) {
+        self.0.insert((day, phase.to_string()), stats);
+    }
+}
+
+impl FromStr for Baseline {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut baseline = Baseline::default();
+        for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let parts: Vec<_> = line.split_whitespace().collect();
+            anyhow::ensure!(parts.len() == 6, "Expected DAY PHASE MIN MEDIAN P95 MAX: {}", line);
+            let nanos = |i: usize| parts[i].parse::<u64>().map(Duration::from_nanos)
+                .with_context(|| format!("Invalid time: {}", line));
+            let stats = Stats{ min: nanos(2)?, median: nanos(3)?, p95: nanos(4)?, max: nanos(5)? };
+            baseline.record(parts[0].parse().with_context(|| format!("Invalid day: {}", line))?, parts[1], stats);
+        }
+        Ok(baseline)
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# DAY PHASE MIN MEDIAN P95 MAX (nanoseconds)")?;
+        for ((day, phase), stats) in self.0.iter() {
+            writeln!(f, "{} {} {} {} {} {}", day, phase, stats.min.as_nanos(), stats.median.as_nanos(),
+                     stats.p95.as_nanos(), stats.max.as_nanos())?;
+        }
+        Ok(())
+    }
+}
+
+/// The relative change in median time from the baseline, e.g. 0.25 for 25% slower.
+pub fn slowdown(baseline: &Stats, current: &Stats) -> f64 {
+    current.median.as_secs_f64() / baseline.median.as_secs_f64().max(f64::MIN_POSITIVE) - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::{self, Answer, Solution};
+
+    fn millis(ms: &[u64]) -> Vec<Duration> {
+        ms.iter().map(|&m| Duration::from_millis(m)).collect()
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats::from_samples(millis(&[5, 1, 4, 2, 3]));
+        assert_eq!(stats, Stats{ min: Duration::from_millis(1), median: Duration::from_millis(3),
+                                 p95: Duration::from_millis(5), max: Duration::from_millis(5) });
+
+        let stats = Stats::from_samples(millis(&(1..=100).collect::<Vec<_>>()));
+        assert_eq!(stats.median, Duration::from_millis(50));
+        assert_eq!(stats.p95, Duration::from_millis(95));
+
+        let stats = Stats::from_samples(millis(&[7]));
+        assert_eq!((stats.min, stats.median, stats.p95, stats.max), (stats.max, stats.max, stats.max, stats.max));
+    }
+
+    #[test]
+    fn baseline_round_trip() {
+        let mut baseline = Baseline::default();
+        baseline.record(15, "part2", Stats::from_samples(millis(&[2000, 2100, 2200])));
+        baseline.record(3, "parse", Stats::from_samples(millis(&[1])));
+        let parsed: Baseline = baseline.to_string().parse().unwrap();
+        assert_eq!(parsed, baseline);
+        assert_eq!(parsed.get(15, "part2").unwrap().median, Duration::from_millis(2100));
+        assert!(parsed.get(15, "part1").is_none());
+
+        assert!("15 part2 1 2 3".parse::<Baseline>().is_err());
+        assert!("15 part2 1 2 3 x".parse::<Baseline>().is_err());
+    }
+
+    #[test]
+    fn compare() {
+        let base = Stats::from_samples(millis(&[100]));
+        assert!((slowdown(&base, &Stats::from_samples(millis(&[125]))) - 0.25).abs() < 1e-9);
+        assert!(slowdown(&base, &Stats::from_samples(millis(&[50]))) < 0.0);
+    }
+
+    struct Counter;
+    impl Solution for Counter {
+        fn parse(_: &str) -> Result<Self> { Ok(Counter) }
+        fn part1(&self) -> Result<Answer> { Ok(1.into()) }
+        fn part2(&self) -> Result<Answer> { anyhow::bail!("unsolved") }
+    }
+
+    #[test]
+    fn bench() {
+        let day = Day{ number: 1, parse: solution::parse::<Counter>, options: Counter::options, generate: Counter::generate };
+        assert!(bench_day(&day, "", &[], 1, 3).is_err()); // part2 fails
+        assert!(bench_day(&day, "", &[], 0, 0).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// The budget for each part, in nanoseconds; 0 means unlimited
+static BUDGET: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    // Budgets for specific days, in place of BUDGET
+    static ref DAY_BUDGETS: Mutex<HashMap<u32, Duration>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    // The deadline of the budgeted() call running on this thread, and the budget it started with
+    static DEADLINE: Cell<Option<(Instant, Duration)>> = const { Cell::new(None) };
+}
+
+/// The error check() returns once the budget is exhausted. Callers add context describing how far
+/// they got.
+#[derive(Debug)]
+pub struct TimedOut(pub Duration);
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+impl TimedOut {
+    /// Whether `error`, or anything that caused it, is a TimedOut.
+    pub fn caused(error: &anyhow::Error) -> bool {
+        error.chain().any(|e| e.is::<TimedOut>())
+    }
+}
+
+/// Sets how long each part may run for, or None for no limit.
+pub fn set_budget(budget: Option<Duration>) {
+    BUDGET.store(budget.map(|b| b.as_nanos() as u64).unwrap_or(0), Ordering::SeqCst);
+}
+
+/// Sets how long each of `day`'s parts may run for, instead of the budget set by set_budget().
+pub fn set_day_budget(day: u32, budget: Duration) {
+    DAY_BUDGETS.lock().unwrap().insert(day, budget);
+}
+
+pub fn budget(day: u32) -> Option<Duration> {
+    if let Some(budget) = DAY_BUDGETS.lock().unwrap().get(&day) {
+        return Some(*budget);
+    }
+    match BUDGET.load(Ordering::SeqCst) {
+        0 => None,
+        nanos => Some(Duration::from_nanos(nanos)),
+    }
+}
+
+/// Calls `f` with `day`'s budget clock running, so that check() fails once it runs out.
+pub fn budgeted<T>(day: u32, f: impl FnOnce() -> T) -> T {
+    within(budget(day), f)
+}
+
+/// Calls `f` with the given budget (or no limit) on the clock.
+pub fn within<T>(budget: Option<Duration>, f: impl FnOnce() -> T) -> T {
+    let deadline = budget.map(|b| (Instant::now() + b, b));
+    let outer = DEADLINE.with(|d| d.replace(deadline));
+    let ret = f();
+    DEADLINE.with(|d| d.set(outer));
+    ret
+}
+
+/// Fails if the current budget has run out. Long-running loops should call this periodically so
+/// that runaway solutions give up rather than hang.
+pub fn check() -> Result<()> {
+    match DEADLINE.with(|d| d.get()) {
+        Some((deadline, budget)) if Instant::now() >= deadline => Err(TimedOut(budget).into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn times_out() {
+        // Each test runs on its own thread, so this deadline doesn't leak into other tests
+        let deadline = Some((Instant::now() + Duration::from_millis(5), Duration::from_millis(5)));
+        DEADLINE.with(|d| d.set(deadline));
+        let mut iterations = 0;
+        let error = loop {
+            if let Err(e) = check().with_context(|| format!("Gave up after {} iterations", iterations)) {
+                break e;
+            }
+            iterations += 1;
+        };
+        assert!(TimedOut::caused(&error));
+        assert!(format!("{:#}", error).ends_with("iterations: Timed out after 5ms"), "{:#}", error);
+
+        // Outside budgeted() calls there's no deadline
+        DEADLINE.with(|d| d.set(None));
+        check().unwrap();
+        assert!(!TimedOut::caused(&anyhow::anyhow!("oops")));
+    }
+}
@@ -0,0 +1,516 @@
+use crate::{answer_cache, batch, bench, budget, config, console, fetch, generate, golden, input, options, report, runner,
+            scaffold, trace, verify, watch, DAYS};
+use crate::solution::{Day, Failure, FailureKind};
+use anyhow::{Context, Result};
+use std::env;
+use std::path::{Path, PathBuf};
+
+// Exit codes, besides 0 for success and 1 for failed checks (e.g. by verify)
+const BAD_INPUT: i32 = 2;
+const NO_SOLUTION: i32 = 3;
+const INTERNAL_ERROR: i32 = 4;
+
+// Like bail!(), but marks the error as bad input
+macro_rules! usage_error {
+    ($($arg:tt)*) => { return Err(Failure::bad_input(anyhow::anyhow!($($arg)*))) };
+}
+
+/// Runs the command line with this process's arguments, then exits.
+pub fn main() {
+    let code = {
+        let _console = console::Console::init();
+        eprintln!(); // split build output from runtime output, without polluting stdout
+        let args: Vec<String> = env::args().collect();
+        // Panics are bugs; the panic hook has already reported it by the time this returns
+        match std::panic::catch_unwind(|| run(&args)) {
+            Ok(Ok(true)) => 0,
+            Ok(Ok(false)) => 1,
+            Ok(Err(e)) => {
+                eprintln!("Error: {:?}", e);
+                match Failure::kind(&e) {
+                    Some(FailureKind::BadInput) => BAD_INPUT,
+                    Some(FailureKind::NoSolution) => NO_SOLUTION,
+                    None => INTERNAL_ERROR,
+                }
+            },
+            Err(_) => INTERNAL_ERROR,
+        }
+    }; // drop the console first, so it can clean up the terminal
+    ::std::process::exit(code);
+}
+
+fn run(args: &[String]) -> Result<bool> {
+    let mut args = args.to_vec();
+    let no_config = take_switch(&mut args, "--no-config");
+    let (mut config, config_path) = match take_flag(&mut args, "--config")? {
+        _ if no_config => (config::Config::default(), None),
+        Some(path) => (config::Config::load(Path::new(&path)).map_err(Failure::bad_input)?, Some(PathBuf::from(path))),
+        None => config::Config::find().map_err(Failure::bad_input)?,
+    };
+    // Command-line flags override the config file
+    match (take_switch(&mut args, "--interactive"), take_switch(&mut args, "--no-interactive")) {
+        (true, true) => usage_error!("--interactive and --no-interactive are mutually exclusive"),
+        (true, false) => config.interactive = Some(true),
+        (false, true) => config.interactive = Some(false),
+        (false, false) => {},
+    }
+    if let Some(multiplier) = take_parsed_flag(&mut args, "--frame-delay")? {
+        config.frame_delay = Some(multiplier);
+    }
+    if let Some(seconds) = take_parsed_flag(&mut args, "--budget")? {
+        config.budget = Some(seconds);
+        config.days.values_mut().for_each(|d| d.budget = None);
+    }
+    if take_switch(&mut args, "--no-cache") {
+        config.cache = Some(false);
+    }
+    if let Some(dir) = take_flag(&mut args, "--data-dir")? {
+        config.data_dir = Some(PathBuf::from(dir));
+    }
+    config.validate().map_err(Failure::bad_input)?;
+    apply_config(&config)?;
+
+    let trace = take_flag(&mut args, "--trace")?;
+    if trace.is_some() {
+        trace::enable();
+    }
+    let result = dispatch(&args, &config, config_path.as_deref());
+    if let Some(trace) = trace {
+        trace::write(Path::new(&trace))?;
+    }
+    result
+}
+
+/// Checks each configured day's options, and applies the runner-wide settings.
+fn apply_config(config: &config::Config) -> Result<()> {
+    for (&number, day_config) in config.days.iter() {
+        let day = match DAYS.iter().find(|d| d.number == number) {
+            Some(day) => day,
+            None => usage_error!("Day {} is configured but hasn't happened yet.", number),
+        };
+        options::Options::parse((day.options)(), &config.day_args(number, &[]))
+            .with_context(|| format!("Invalid options for day {} in {}", number, config::FILE_NAME))
+            .map_err(Failure::bad_input)?;
+        if let Some(seconds) = day_config.budget {
+            budget::set_day_budget(number, std::time::Duration::from_secs_f64(seconds));
+        }
+    }
+    if let Some(interactive) = config.interactive {
+        console::Console::set_interactive(interactive);
+    }
+    if let Some(multiplier) = config.frame_delay {
+        console::Console::set_frame_delay(multiplier);
+    }
+    budget::set_budget(config.budget.map(std::time::Duration::from_secs_f64));
+    if config.cache == Some(false) {
+        answer_cache::disable();
+    }
+    if let Some(dir) = &config.data_dir {
+        input::set_data_dir(dir);
+    }
+    Ok(())
+}
+
+fn dispatch(args: &[String], config: &config::Config, config_path: Option<&Path>) -> Result<bool> {
+    match args.get(1).map(|a| a.as_str()) {
+        None => {
+            usage(&args[0]);
+            Ok(true)
+        },
+        Some("verify") => verify(args[2..].to_vec(), config),
+        Some("bench") => bench(args[2..].to_vec(), config),
+        Some("batch") => batch(args[2..].to_vec(), config),
+        Some("golden") => golden(args[2..].to_vec(), config),
+        Some("generate") => generate(args[2..].to_vec()),
+        Some("fetch") => fetch(args[2..].to_vec()),
+        Some("new-day") => new_day(args[2..].to_vec()),
+        Some("report") => report(args[2..].to_vec(), config),
+        Some("config") => show_config(args[2..].to_vec(), config, config_path),
+        Some(_) => solve(args[1..].to_vec(), config),
+    }
+}
+
+fn usage(binary: &str) {
+    println!("Usage: {} [--input PATH|-] [--format text|json] [--verbose] DAY_OF_ADVENT [OPTIONS...|--help]", binary);
+    println!("       {} [--input PATH] [--verbose] --watch DAY_OF_ADVENT [OPTIONS...]", binary);
+    println!("       {} [--format text|json] [--jobs N] all|FIRST-LAST", binary);
+    println!("       {} verify [all|FIRST-LAST] [--answers PATH]", binary);
+    println!("       {} bench [all|FIRST-LAST] [--runs N] [--warmup N] [--save PATH]", binary);
+    println!("             [--baseline PATH] [--threshold PERCENT]");
+    println!("       {} batch [--jobs N] DAY_OF_ADVENT DIR [OPTIONS...]", binary);
+    println!("       {} fetch [all|FIRST-LAST]", binary);
+    println!("       {} new-day DAY_OF_ADVENT [SOLUTION_NAME]", binary);
+    println!("       {} golden [all|FIRST-LAST] [--jobs N] [--update]", binary);
+    println!("       {} generate DAY_OF_ADVENT [--seed S] [--size N]", binary);
+    println!("       {} report [all|FIRST-LAST] --html PATH", binary);
+    println!("       {} config show", binary);
+    println!();
+    println!("Exits with {} for bad input or arguments, {} if no solution was found, and {} for internal errors.",
+             BAD_INPUT, NO_SOLUTION, INTERNAL_ERROR);
+    println!();
+    println!("Missing inputs are downloaded into data/ if {} is set to a session token.", fetch::SESSION_VAR);
+    println!("Pass --trace PATH to any command to save a Chrome trace of the run (see chrome://tracing).");
+    println!("Pass --interactive or --no-interactive to toggle animations (on by default in debug builds),");
+    println!("and --frame-delay MULTIPLIER to speed them up or slow them down, e.g. 0.1 or 2.");
+    println!("Pass --budget SECONDS to give up on any part that runs longer than that.");
+    println!("Answers are cached per input and build; pass --no-cache to solve everything again.");
+    println!("Pass --verbose with a single day to also print how each answer was reached (this skips the cache).");
+    println!("Pass --data-dir DIR to read inputs from somewhere other than data/.");
+    println!();
+    println!("Defaults for these settings, --jobs, --format and each day's options are read from {}", config::FILE_NAME);
+    println!("in the current directory (or --config PATH, or --no-config to ignore it); command-line flags take");
+    println!("precedence.");
+}
+
+/// Removes `flag` and the value following it from args, returning the value if the flag was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Result<Option<String>> {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => {
+            if i+1 >= args.len() { usage_error!("{} requires a value", flag); }
+            let value = args.remove(i+1);
+            args.remove(i);
+            Ok(Some(value))
+        },
+        None => Ok(None),
+    }
+}
+
+/// Removes a flag that takes no value from args, returning whether it was present.
+fn take_switch(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|a| a != flag);
+    args.len() != len
+}
+
+/// Like take_flag(), but parses the value.
+fn take_parsed_flag<T>(args: &mut Vec<String>, flag: &str) -> Result<Option<T>>
+        where T: std::str::FromStr, T::Err: std::error::Error + Send + Sync + 'static {
+    match take_flag(args, flag)? {
+        Some(value) => Ok(Some(value.parse().with_context(|| format!("Invalid {}: {}", flag, value))
+            .map_err(Failure::bad_input)?)),
+        None => Ok(None),
+    }
+}
+
+fn solve(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let source = take_flag(&mut args, "--input")?
+        .map(|i| input::Source::from_arg(&i)).unwrap_or(input::Source::Default);
+    let json = match take_flag(&mut args, "--format")?.as_deref().or(config.format.as_deref()) {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(format) => usage_error!("Unknown format: {}", format),
+    };
+    let jobs = take_parsed_flag(&mut args, "--jobs")?.or(config.jobs).unwrap_or(1);
+    let watch = take_switch(&mut args, "--watch");
+    let verbose = take_switch(&mut args, "--verbose");
+    if args.is_empty() { usage_error!("No day specified"); }
+    let days = runner::select(DAYS, &args[0])?;
+    if args[1..].iter().any(|a| a == "--help") {
+        for day in days {
+            match (day.options)() {
+                [] => println!("Day {} takes no options", day.number),
+                opts => println!("Day {} options:\n{}", day.number, options::help(opts)),
+            }
+        }
+        return Ok(true);
+    }
+    if days.len() > 1 && (source != input::Source::Default || args.len() > 1) {
+        usage_error!("--input and day arguments can only be used with a single day");
+    }
+    if verbose {
+        if days.len() > 1 || json { usage_error!("--verbose can only be used with a single day and text output"); }
+        // Cached answers don't come with details
+        answer_cache::disable();
+    }
+    if watch {
+        if days.len() > 1 || json { usage_error!("--watch can only be used with a single day and text output"); }
+        return watch_day(days[0], &source, &config.day_args(days[0].number, &args[1..]), verbose);
+    }
+    if jobs > 1 {
+        console::Console::suppress_interactive();
+    }
+    let solve_day = |i: usize| runner::run_day(days[i], &source, &config.day_args(days[i].number, &args[1..]));
+
+    if !json {
+        if let [day] = days[..] {
+            solve_single(day, &source, &config.day_args(day.number, &args[1..]), verbose)?;
+            return Ok(true);
+        }
+    }
+
+    let mut results = Vec::new();
+    runner::in_order(days.len(), jobs, solve_day, |i, result| {
+        if json {
+            for record in runner::json_records(days[i].number, &result) {
+                println!("{}", record);
+            }
+        }
+        results.push((days[i].number, result));
+    });
+    if !json {
+        println!("{}", runner::summary_table(&results));
+    }
+    match runner::failures(&results) {
+        Some(e) => Err(e),
+        None => Ok(true),
+    }
+}
+
+fn solve_single(day: &Day, source: &input::Source, args: &[String], verbose: bool) -> Result<()> {
+    let _span = trace::Span::enter(format!("Day {}", day.number));
+    let mut solver = runner::Solver::new(day, source, args)?;
+    for part in 1..=2 {
+        let result = elapsed!(format!("Part {}", part), solver.part(part))?;
+        if verbose {
+            for line in result.details.iter() {
+                println!("{}", line);
+            }
+        }
+        println!("Part {}: {}", part, result.answer?);
+    }
+    Ok(())
+}
+
+/// Solves the day, then again every time its input (or examples) change, until interrupted.
+fn watch_day(day: &Day, source: &input::Source, args: &[String], verbose: bool) -> Result<bool> {
+    let mut watcher = watch::Watcher::new(|| watch::files(day.number, source)).map_err(Failure::bad_input)?;
+    loop {
+        console::Console::clear_screen();
+        println!("Watching day {} for changes; press Ctrl-C to stop\n", day.number);
+        if let Err(e) = solve_single(day, source, args, verbose) {
+            println!("Error: {:?}", e);
+        }
+        watcher.wait()?;
+    }
+}
+
+fn verify(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let answers = take_flag(&mut args, "--answers")?.map(PathBuf::from)
+        .unwrap_or_else(verify::path);
+    let expected = verify::Expected::load(&answers)?;
+    let days = runner::select(DAYS, args.first().map(|a| a.as_str()).unwrap_or("all"))?;
+
+    let mut passed = true;
+    for day in days {
+        match runner::run_day(day, &input::Source::Default, &config.day_args(day.number, &[])) {
+            Ok(report) => {
+                for (part, result) in [(1, &report.part1), (2, &report.part2)].iter() {
+                    let status = expected.check(day.number, *part, &result.answer);
+                    passed &= status.passed();
+                    println!("Day {:>2} part {}: {}", day.number, part, status);
+                }
+            },
+            Err(e) => {
+                passed = false;
+                println!("Day {:>2}: ERROR {:#}", day.number, e);
+            },
+        }
+    }
+    Ok(passed)
+}
+
+/// Compares what each day prints against its checked-in golden output, or rewrites the golden files
+/// with --update.
+fn golden(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let update = take_switch(&mut args, "--update");
+    let jobs = take_parsed_flag(&mut args, "--jobs")?.or(config.jobs).unwrap_or(1);
+    let days = runner::select(DAYS, args.first().map(|a| a.as_str()).unwrap_or("all"))?;
+
+    let mut passed = true;
+    let mut failure = None;
+    runner::in_order(days.len(), jobs, |i| golden::capture(days[i].number), |i, output| {
+        let path = golden::path(days[i].number);
+        let status = match output {
+            Err(e) => golden::Status::Error(format!("{:#}", e)),
+            Ok(actual) => {
+                let status = match std::fs::read_to_string(&path) {
+                    Ok(expected) => golden::compare(&expected, &actual),
+                    Err(_) => golden::Status::Missing,
+                };
+                if !update || status == golden::Status::Pass {
+                    status
+                } else if let Err(e) = std::fs::write(&path, actual) {
+                    failure.get_or_insert(anyhow::Error::new(e).context(format!("Failed to write {}", path.display())));
+                    golden::Status::Error("not updated".into())
+                } else {
+                    golden::Status::Updated
+                }
+            },
+        };
+        passed &= status.passed();
+        println!("Day {:>2}: {}", days[i].number, status);
+    });
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(passed),
+    }
+}
+
+/// Prints a random input for a day, and (to stderr) the answers it should produce.
+fn generate(mut args: Vec<String>) -> Result<bool> {
+    let seed = take_parsed_flag(&mut args, "--seed")?.unwrap_or(1);
+    let size = take_parsed_flag(&mut args, "--size")?.unwrap_or(10);
+    if args.is_empty() { usage_error!("No day specified"); }
+    if args.len() > 1 { usage_error!("Unexpected arguments: {}", args[1..].join(" ")); }
+    let day = match runner::select(DAYS, &args[0])?[..] {
+        [day] => day,
+        _ => usage_error!("generate only generates a single day"),
+    };
+    let generated = (day.generate)(&mut generate::Rng::new(seed), size)
+        .with_context(|| format!("Can't generate an input for day {}", day.number)).map_err(Failure::bad_input)?;
+    print!("{}", generated.input);
+    eprintln!("Expected part 1: {}", generated.part1);
+    eprintln!("Expected part 2: {}", generated.part2);
+    Ok(true)
+}
+
+/// Solves one day for every input in a directory, printing a CSV row per input. Failing inputs are
+/// reported in their row rather than stopping the batch.
+fn batch(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let jobs = take_parsed_flag(&mut args, "--jobs")?.or(config.jobs).unwrap_or(1);
+    if args.len() < 2 { usage_error!("batch requires a day and a directory of inputs"); }
+    let days = runner::select(DAYS, &args[0])?;
+    let day = match days[..] {
+        [day] => day,
+        _ => usage_error!("batch only runs a single day"),
+    };
+    let day_args = &config.day_args(day.number, &args[2..]);
+    // Check the options up front, rather than reporting the same error for every input
+    options::Options::parse((day.options)(), day_args).map_err(Failure::bad_input)?;
+    let inputs = batch::inputs(Path::new(&args[1])).map_err(Failure::bad_input)?;
+    console::Console::suppress_interactive(); // frames would be interleaved with the CSV
+
+    println!("{}", batch::HEADER);
+    let mut passed = true;
+    runner::in_order(inputs.len(), jobs,
+        |i| runner::run_day(day, &input::Source::File(inputs[i].clone()), day_args),
+        |i, result| {
+            passed &= result.as_ref().map(|r| r.part1.answer.is_ok() && r.part2.answer.is_ok()).unwrap_or(false);
+            let file = inputs[i].file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+            println!("{}", batch::row(&file, &result));
+        });
+    Ok(passed)
+}
+
+/// Solves each day and writes the answers, timings and each day's final interactive frame to an
+/// HTML page.
+fn report(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let path = match take_flag(&mut args, "--html")? {
+        Some(path) => PathBuf::from(path),
+        None => usage_error!("report requires --html PATH"),
+    };
+    if args.len() > 1 { usage_error!("report doesn't take day arguments; set them in {}", config::FILE_NAME); }
+    let days = runner::select(DAYS, args.first().map(|a| a.as_str()).unwrap_or("all"))?;
+    // Cached answers would have no timings or frames to show
+    answer_cache::disable();
+    console::Console::suppress_interactive();
+    console::Console::capture_frames();
+
+    let mut results = Vec::new();
+    let mut passed = true;
+    for day in days {
+        console::Console::clear_colors();
+        console::Console::take_frame();
+        let result = runner::run_day(day, &input::Source::Default, &config.day_args(day.number, &[]));
+        let solved = result.as_ref().map(|r| r.part1.answer.is_ok() && r.part2.answer.is_ok()).unwrap_or(false);
+        passed &= solved;
+        println!("Day {:>2}: {}", day.number, if solved { "solved" } else { "failed" });
+        results.push((day.number, result, console::Console::take_frame()));
+    }
+    std::fs::write(&path, report::html(&results)).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(passed)
+}
+
+/// Prints the settings in effect, after applying the config file and any command-line overrides.
+fn show_config(args: Vec<String>, config: &config::Config, path: Option<&Path>) -> Result<bool> {
+    match args.first().map(|a| a.as_str()) {
+        Some("show") if args.len() == 1 => {},
+        _ => usage_error!("Usage: config show"),
+    }
+    match path {
+        Some(path) => println!("# Read from {}", path.display()),
+        None => println!("# No {} found; using defaults", config::FILE_NAME),
+    }
+    let effective = config::Config{
+        interactive: Some(console::Console::interactive()),
+        frame_delay: config.frame_delay.or(Some(1.0)),
+        cache: config.cache.or(Some(true)),
+        data_dir: Some(input::data_dir()),
+        jobs: config.jobs.or(Some(1)),
+        format: config.format.clone().or_else(|| Some("text".to_string())),
+        ..config.clone()
+    };
+    print!("{}", effective);
+    Ok(true)
+}
+
+fn bench(mut args: Vec<String>, config: &config::Config) -> Result<bool> {
+    let runs = take_parsed_flag(&mut args, "--runs")?.unwrap_or(10);
+    let warmup = take_parsed_flag(&mut args, "--warmup")?.unwrap_or(2);
+    let threshold: f64 = take_parsed_flag(&mut args, "--threshold")?.unwrap_or(10.0);
+    let save = take_flag(&mut args, "--save")?.map(PathBuf::from);
+    let baseline = match take_flag(&mut args, "--baseline")? {
+        Some(path) => Some(bench::Baseline::load(&PathBuf::from(path))?),
+        None => None,
+    };
+    let mut updated = match &save {
+        Some(path) => bench::Baseline::load(path)?,
+        None => bench::Baseline::default(),
+    };
+    let days = runner::select(DAYS, args.first().map(|a| a.as_str()).unwrap_or("all"))?;
+    let day_args = if args.len() > 1 { &args[1..] } else { &[] };
+    if days.len() > 1 && !day_args.is_empty() {
+        usage_error!("Day arguments can only be used with a single day");
+    }
+    // Otherwise frames would be rendered, and their delays slept through, on every run
+    console::Console::suppress_interactive();
+
+    let mut passed = true;
+    for day in days {
+        let input = input::load(day.number, &input::Source::Default)?;
+        println!("Day {} ({} runs after {} warm-up)", day.number, runs, warmup);
+        let stats = bench::bench_day(day, &input, &config.day_args(day.number, day_args), warmup, runs)
+            .with_context(|| format!("Day {} failed", day.number))?;
+        for (phase, stats) in bench::PHASES.iter().zip(stats.iter()) {
+            let comparison = match baseline.as_ref().and_then(|b| b.get(day.number, phase)) {
+                Some(base) => {
+                    let slowdown = bench::slowdown(base, stats) * 100.0;
+                    let slower = slowdown > threshold;
+                    passed &= !slower;
+                    format!("  {:+6.1}% vs. {:.1?}{}", slowdown, base.median, if slower { " SLOWER" } else { "" })
+                },
+                None => String::new(),
+            };
+            println!("  {:<5}  {}{}", phase, stats, comparison);
+            updated.record(day.number, phase, *stats);
+        }
+    }
+
+    if let Some(path) = save {
+        updated.save(&path)?;
+    }
+    Ok(passed)
+}
+
+fn fetch(args: Vec<String>) -> Result<bool> {
+    let cache = input::cache();
+    for day in runner::select(DAYS, args.first().map(|a| a.as_str()).unwrap_or("all"))? {
+        cache.load(day.number)?;
+        println!("Day {:>2}: {}", day.number, cache.path(day.number).display());
+    }
+    Ok(true)
+}
+
+fn new_day(args: Vec<String>) -> Result<bool> {
+    let day: u32 = match args.first() {
+        Some(day) => day.parse().with_context(|| format!("Should be a natural number: {}", day))
+            .map_err(Failure::bad_input)?,
+        None => usage_error!("No day specified"),
+    };
+    let name = args.get(1).cloned().unwrap_or_else(|| format!("Day{:02}", day));
+    for path in scaffold::create(Path::new(env!("CARGO_MANIFEST_DIR")), day, &name)? {
+        println!("Wrote {}", path.display());
+    }
+    Ok(true)
+}
@@ -0,0 +1,343 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The configuration file looked for in the working directory, then the checkout.
+pub const FILE_NAME: &str = "advent.toml";
+
+/// Runner settings from advent.toml; anything unset uses the runner's default. A small subset of
+/// TOML is supported: top-level settings, `[dayNN]` sections, and string, number, boolean and
+/// number-array values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub interactive: Option<bool>,
+    pub frame_delay: Option<f64>,
+    /// Seconds each part may run for
+    pub budget: Option<f64>,
+    pub cache: Option<bool>,
+    pub data_dir: Option<PathBuf>,
+    pub jobs: Option<usize>,
+    pub format: Option<String>,
+    pub days: BTreeMap<u32, DayConfig>,
+}
+
+/// Settings for one day, from its `[dayNN]` section.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DayConfig {
+    pub budget: Option<f64>,
+    /// Values for the day's options, e.g. `depth = 6` for `--depth 6`
+    pub options: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Bool(bool),
+    Number(String),
+    Str(String),
+    Numbers(Vec<String>),
+}
+
+impl Value {
+    fn parse(value: &str) -> Result<Value> {
+        match value {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {},
+        }
+        if let Some(s) = value.strip_prefix('"') {
+            let s = s.strip_suffix('"').context("Unterminated string")?;
+            ensure!(!s.contains('"') && !s.contains('\\'), "Quotes and escapes aren't supported in strings");
+            return Ok(Value::Str(s.to_string()));
+        }
+        if let Some(list) = value.strip_prefix('[') {
+            let list = list.strip_suffix(']').context("Unterminated array")?;
+            let numbers = list.split(',').map(|n| n.trim()).filter(|n| !n.is_empty())
+                .map(|n| Value::number(n).map(|_| n.to_string())).collect::<Result<_>>()?;
+            return Ok(Value::Numbers(numbers));
+        }
+        Value::number(value).map(|_| Value::Number(value.to_string()))
+    }
+
+    fn number(value: &str) -> Result<f64> {
+        value.parse().with_context(|| format!("Expected a string, number, boolean or array of numbers: {}", value))
+    }
+
+    fn bool(&self) -> Result<bool> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            _ => bail!("Should be true or false"),
+        }
+    }
+
+    fn float(&self) -> Result<f64> {
+        match self {
+            Value::Number(n) => Ok(n.parse()?),
+            _ => bail!("Should be a number"),
+        }
+    }
+
+    fn string(&self) -> Result<String> {
+        match self {
+            Value::Str(s) => Ok(s.clone()),
+            _ => bail!("Should be a quoted string"),
+        }
+    }
+
+    /// The value as a command-line argument.
+    fn arg(&self) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) | Value::Str(n) => n.clone(),
+            Value::Numbers(ns) => ns.join(","),
+        }
+    }
+}
+
+impl Config {
+    /// Loads advent.toml from the working directory or, failing that, the checkout. Returns the
+    /// default (empty) config if there's no file in either place.
+    pub fn find() -> Result<(Config, Option<PathBuf>)> {
+        let candidates = [PathBuf::from(FILE_NAME), Path::new(env!("CARGO_MANIFEST_DIR")).join(FILE_NAME)];
+        match candidates.iter().find(|p| p.is_file()) {
+            Some(path) => Ok((Config::load(path)?, Some(path.clone()))),
+            None => Ok((Config::default(), None)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Config> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Config::parse(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Config> {
+        let mut config = Config::default();
+        let mut section = None;
+        let mut seen = std::collections::HashSet::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(comment) if !line[..comment].contains('"') => &line[..comment],
+                _ => line,
+            }.trim();
+            if line.is_empty() { continue; }
+            let parsed = if let Some(header) = line.strip_prefix('[') {
+                Config::section(header).map(|day| {
+                    section = Some(day);
+                    config.days.entry(day).or_default();
+                })
+            } else {
+                match line.find('=') {
+                    Some(eq) => {
+                        let (key, value) = (line[..eq].trim(), line[eq+1..].trim());
+                        if section.is_none() && !GLOBAL_KEYS.contains(&key) {
+                            Err(anyhow::anyhow!("Unknown setting {}; expected one of {}", key, GLOBAL_KEYS.join(", ")))
+                        } else if seen.insert((section, key.to_string())) {
+                            Value::parse(value).and_then(|value| config.set(section, key, value))
+                                .with_context(|| format!("Invalid {}", key))
+                        } else {
+                            Err(anyhow::anyhow!("{} is set more than once", key))
+                        }
+                    },
+                    None => Err(anyhow::anyhow!("Expected KEY = VALUE or [dayNN]: {}", line)),
+                }
+            };
+            parsed.with_context(|| format!("Line {}", i + 1))?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn section(header: &str) -> Result<u32> {
+        let name = header.strip_suffix(']').context("Unterminated section header")?.trim();
+        name.strip_prefix("day").and_then(|d| d.parse().ok()).filter(|d| (1..=25).contains(d))
+            .with_context(|| format!("Sections should be days, like [day19]: [{}]", name))
+    }
+
+    fn set(&mut self, section: Option<u32>, key: &str, value: Value) -> Result<()> {
+        if let Some(day) = section {
+            let day = self.days.get_mut(&day).expect("Created when the section started");
+            match key {
+                "budget" => day.budget = Some(value.float()?),
+                _ => day.options.push((key.to_string(), value.arg())),
+            }
+            return Ok(());
+        }
+        match key {
+            "interactive" => self.interactive = Some(value.bool()?),
+            "frame-delay" => self.frame_delay = Some(value.float()?),
+            "budget" => self.budget = Some(value.float()?),
+            "cache" => self.cache = Some(value.bool()?),
+            "data-dir" => self.data_dir = Some(PathBuf::from(value.string()?)),
+            "jobs" => self.jobs = Some(value.arg().parse().context("Should be a positive integer")?),
+            "format" => self.format = Some(value.string()?),
+            _ => unreachable!("Checked against GLOBAL_KEYS"),
+        }
+        Ok(())
+    }
+
+    /// Checks the settings are in range; used for both the file and command-line overrides.
+    pub fn validate(&self) -> Result<()> {
+        let positive = |budget: Option<f64>| budget.map(|b| b > 0.0 && b.is_finite()).unwrap_or(true);
+        if let Some(delay) = self.frame_delay {
+            ensure!(delay >= 0.0 && delay.is_finite(), "frame-delay should be a non-negative multiplier: {}", delay);
+        }
+        ensure!(positive(self.budget), "budget should be a positive number of seconds: {}", self.budget.unwrap_or_default());
+        for (day, config) in self.days.iter() {
+            ensure!(positive(config.budget), "day{:02} budget should be a positive number of seconds", day);
+        }
+        ensure!(self.jobs != Some(0), "jobs should be at least 1");
+        if let Some(format) = &self.format {
+            ensure!(format == "text" || format == "json", "Unknown format: {}", format);
+        }
+        Ok(())
+    }
+
+    /// The day's configured options as arguments, followed by `args`. Options set in `args`
+    /// override the configured ones.
+    pub fn day_args(&self, day: u32, args: &[String]) -> Vec<String> {
+        let mut merged = Vec::new();
+        if let Some(config) = self.days.get(&day) {
+            for (name, value) in config.options.iter() {
+                let flag = format!("--{}", name);
+                if !args.iter().any(|a| *a == flag || a.starts_with(&format!("{}=", flag))) {
+                    merged.push(flag);
+                    merged.push(value.clone());
+                }
+            }
+        }
+        merged.extend(args.iter().cloned());
+        merged
+    }
+}
+
+const GLOBAL_KEYS: &[&str] = &["interactive", "frame-delay", "budget", "cache", "data-dir", "jobs", "format"];
+
+// Numbers and options are written as they were read, so e.g. "seed = [0,3,6]" round-trips as a list
+fn format_value(value: &str) -> String {
+    if value.parse::<f64>().is_ok() || value == "true" || value == "false" {
+        value.to_string()
+    } else if value.split(',').all(|n| n.trim().parse::<f64>().is_ok()) {
+        format!("[{}]", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+/// Renders the config as TOML; unset settings are written as comments.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let settings = [
+            self.interactive.map(|v| v.to_string()),
+            self.frame_delay.map(|v| v.to_string()),
+            self.budget.map(|v| v.to_string()),
+            self.cache.map(|v| v.to_string()),
+            self.data_dir.as_ref().map(|v| format!("\"{}\"", v.display())),
+            self.jobs.map(|v| v.to_string()),
+            self.format.as_ref().map(|v| format!("\"{}\"", v)),
+        ];
+        for (key, value) in GLOBAL_KEYS.iter().zip(settings.iter()) {
+            match value {
+                Some(value) => writeln!(f, "{} = {}", key, value)?,
+                None => writeln!(f, "# {} is not set", key)?,
+            }
+        }
+        for (day, config) in self.days.iter() {
+            writeln!(f, "\n[day{:02}]", day)?;
+            if let Some(budget) = config.budget {
+                writeln!(f, "budget = {}", budget)?;
+            }
+            for (name, value) in config.options.iter() {
+                writeln!(f, "{} = {}", name, format_value(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+# Shared settings
+interactive = false
+frame-delay = 0.5  # twice as fast
+budget = 10
+data-dir = "inputs"
+jobs = 4
+
+[day19]
+depth = 6
+budget = 30
+
+[day15]
+seed = [0, 3, 6]
+"#;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(EXAMPLE).unwrap();
+        assert_eq!(config.interactive, Some(false));
+        assert_eq!(config.frame_delay, Some(0.5));
+        assert_eq!(config.budget, Some(10.0));
+        assert_eq!(config.cache, None);
+        assert_eq!(config.data_dir, Some(PathBuf::from("inputs")));
+        assert_eq!(config.jobs, Some(4));
+        assert_eq!(config.days[&19], DayConfig{ budget: Some(30.0), options: vec!(("depth".into(), "6".into())) });
+        assert_eq!(config.days[&15].options, vec!(("seed".into(), "0,3,6".into())));
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn overrides() {
+        let config = Config::parse(EXAMPLE).unwrap();
+        assert_eq!(config.day_args(19, &[]), args(&["--depth", "6"]));
+        assert_eq!(config.day_args(19, &args(&["--depth", "8"])), args(&["--depth", "8"]));
+        assert_eq!(config.day_args(19, &args(&["--depth=8"])), args(&["--depth=8"]));
+        assert_eq!(config.day_args(15, &args(&["--help"])), args(&["--seed", "0,3,6", "--help"]));
+        assert_eq!(config.day_args(1, &args(&["--target", "5"])), args(&["--target", "5"]));
+    }
+
+    parameterized_test::create!{invalid, (text, message), {
+        let error = format!("{:#}", Config::parse(text).unwrap_err());
+        assert!(error.contains(message), "{}", error);
+    }}
+    invalid!{
+        unknown: ("colour = true", "Line 1: Unknown setting colour"),
+        wrong_type: ("\n\ninteractive = 1", "Line 3: Invalid interactive: Should be true or false"),
+        not_a_value: ("jobs = many", "Expected a string, number, boolean or array of numbers: many"),
+        bad_section: ("[general]", "Sections should be days, like [day19]: [general]"),
+        no_day_26: ("[day26]", "Sections should be days"),
+        repeated: ("[day19]\ndepth = 5\ndepth = 6", "Line 3: depth is set more than once"),
+        not_a_setting: ("interactive", "Expected KEY = VALUE or [dayNN]: interactive"),
+        negative_budget: ("budget = -1", "budget should be a positive number of seconds"),
+        format: ("format = \"xml\"", "Unknown format: xml"),
+        no_jobs: ("jobs = 0", "jobs should be at least 1"),
+    }
+
+    #[test]
+    fn display() {
+        let config = Config::parse(EXAMPLE).unwrap();
+        assert_eq!(config.to_string(), "\
+interactive = false
+frame-delay = 0.5
+budget = 10
+# cache is not set
+data-dir = \"inputs\"
+jobs = 4
+# format is not set
+
+[day15]
+seed = [0,3,6]
+
+[day19]
+budget = 30
+depth = 6
+");
+        assert_eq!(Config::parse(&config.to_string()).unwrap(), config);
+    }
+}
@@ -1,36 +1,53 @@
-macro_rules! interactive {
-    () => {
-        cfg!(feature = "interactive") || cfg!(debug_assertions) && !cfg!(test)
-    };
-}
-
 #[cfg(feature="timing")]
+#[macro_export]
 macro_rules! elapsed {
     ($expression:expr) => { elapsed!(stringify!($expression), $expression) };
     ($desc:expr, $expression:expr) => { {
         println!("\u{001B}[36m[{}...\u{001B}[0m", $desc);
         let start = std::time::Instant::now();
+        let memory = $crate::memory::Scope::start();
         let ret = $expression;
-        println!("\u{001B}[36mElapsed: {:?}]\u{001B}[0m", start.elapsed());
+        println!("\u{001B}[36mElapsed: {:?}; {}]\u{001B}[0m", start.elapsed(), memory.usage());
         ret
     } };
 }
 #[cfg(not(feature="timing"))]
+#[macro_export]
 macro_rules! elapsed {
     ($expression:expr) => { $expression };
     ($desc:expr, $expression:expr) => { $expression };
 }
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::Duration;
 
 static RESET_LINES: AtomicUsize = AtomicUsize::new(0);
+static SUPPRESSED: AtomicBool = AtomicBool::new(false);
+// Interactive output is on by default in debug builds (except tests) or with the interactive feature
+static INTERACTIVE: AtomicBool =
+    AtomicBool::new(cfg!(feature = "interactive") || cfg!(debug_assertions) && !cfg!(test));
+static CURSOR_HIDDEN: AtomicBool = AtomicBool::new(false);
+// Whether a Console guard is alive to restore the cursor; if not it's never hidden
+static GUARDED: AtomicBool = AtomicBool::new(false);
+// The bits of an f64 multiplier applied to every frame's delay
+static FRAME_DELAY: AtomicU64 = AtomicU64::new(1.0_f64.to_bits());
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // The detail() lines recorded on this thread, while a record_details() call is running
+    static DETAILS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
 
 lazy_static! {
-    static ref TRANSFORMS: Mutex<HashMap<char, String>> = Mutex::new(HashMap::new());
+    static ref COLORS: Mutex<HashMap<char, Color>> = Mutex::new(HashMap::new());
+    static ref LAST_FRAME: Mutex<Option<Frame>> = Mutex::new(None);
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Color {
     /*BLACK, */RED, GREEN, YELLOW, BLUE, /*MAGENTA, CYAN,*/ GREY,
 }
@@ -49,52 +66,142 @@ impl Color {
         };
         format!("\u{001B}[{}m█\u{001B}[0m", code)
     }
+
+    /// The CSS equivalent of the ANSI color, for rendering frames outside a terminal.
+    pub fn css(&self) -> &'static str {
+        match self {
+            Color::RED => "#cd3131",
+            Color::GREEN => "#0dbc79",
+            Color::YELLOW => "#e5e510",
+            Color::BLUE => "#2472c8",
+            Color::GREY => "#a0a0a0",
+        }
+    }
+}
+
+/// A captured interactive frame, along with the colors its characters are displayed in.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub text: String,
+    pub colors: HashMap<char, Color>,
 }
 
 pub struct Console;
 
 impl Console {
+    /// Returns a guard that restores the terminal once it's dropped. The cursor is hidden when the
+    /// first interactive frame is displayed, but only while a guard exists.
     #[inline]
     pub fn init() -> Console {
-        if interactive!() {
-            print!("\u{001B}[?25l"); // hide cursor
-        }
+        GUARDED.store(true, Ordering::SeqCst);
         Console
     }
 
+    /// Turns interactive output on or off, overriding the build's default.
+    pub fn set_interactive(interactive: bool) {
+        INTERACTIVE.store(interactive, Ordering::SeqCst);
+    }
+
+    /// Scales the delay after each interactive frame, e.g. 0.5 to animate twice as fast, or 0 to
+    /// not pause at all.
+    pub fn set_frame_delay(multiplier: f64) {
+        FRAME_DELAY.store(multiplier.to_bits(), Ordering::SeqCst);
+    }
+
+    pub fn interactive() -> bool {
+        INTERACTIVE.load(Ordering::SeqCst) && !Console::suppressed()
+    }
+
+    /// Disables interactive output for the rest of the run, e.g. because multiple days are running
+    /// concurrently and their frames would clobber each other.
+    pub fn suppress_interactive() {
+        SUPPRESSED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn suppressed() -> bool {
+        SUPPRESSED.load(Ordering::SeqCst)
+    }
+
     pub fn colorize_char(c: char, color: Color) {
-        if interactive!() {
-            TRANSFORMS.lock().unwrap().insert(c,  color.ansi());
-        }
+        COLORS.lock().unwrap().insert(c, color);
+    }
+
+    /// Forgets every colorize_char() mapping, e.g. before running another day.
+    pub fn clear_colors() {
+        COLORS.lock().unwrap().clear();
     }
 
-    pub fn interactive_display(lazy: impl ToString, delay: std::time::Duration) {
-        if interactive!() {
-            let transforms = TRANSFORMS.lock().unwrap();
-            let mut str = lazy.to_string();
-            if !transforms.is_empty() {
-                str = str.chars().map(|c| transforms.get(&c).unwrap_or(&c.to_string()).clone()).collect::<Vec<_>>().concat();
+    /// Keeps the most recent interactive_display() frame for take_frame(), even when interactive
+    /// output is disabled.
+    pub fn capture_frames() {
+        CAPTURING.store(true, Ordering::SeqCst);
+    }
+
+    /// The last frame displayed since capture_frames() was called, or since the last call.
+    pub fn take_frame() -> Option<Frame> {
+        LAST_FRAME.lock().unwrap().take()
+    }
+
+    /// Records a line describing how an answer was reached, such as the values it was derived
+    /// from. The runner prints these with --verbose; otherwise they're discarded.
+    pub fn detail(line: impl ToString) {
+        DETAILS.with(|d| if let Some(details) = d.borrow_mut().as_mut() { details.push(line.to_string()) });
+    }
+
+    /// Calls `f`, returning the lines it passed to detail() along with its result.
+    pub fn record_details<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+        let outer = DETAILS.with(|d| d.replace(Some(Vec::new())));
+        let ret = f();
+        let details = DETAILS.with(|d| d.replace(outer)).unwrap_or_default();
+        (ret, details)
+    }
+
+    pub fn interactive_display(lazy: impl ToString, delay: Duration) {
+        let interactive = Console::interactive();
+        if !interactive && !CAPTURING.load(Ordering::SeqCst) { return; }
+        let mut str = lazy.to_string();
+        let colors = COLORS.lock().unwrap().clone();
+        if CAPTURING.load(Ordering::SeqCst) {
+            *LAST_FRAME.lock().unwrap() = Some(Frame{ text: str.clone(), colors: colors.clone() });
+        }
+        if interactive {
+            if GUARDED.load(Ordering::SeqCst) && !CURSOR_HIDDEN.swap(true, Ordering::SeqCst) {
+                print!("\u{001B}[?25l"); // hide cursor
+            }
+            if !colors.is_empty() {
+                str = str.chars().map(|c| colors.get(&c).map(|c| c.ansi()).unwrap_or_else(|| c.to_string())).collect::<Vec<_>>().concat();
             }
             let lines = str.chars().filter(|&c| c == '\n').count()+1;
             // https://doc.rust-lang.org/std/sync/atomic/struct.AtomicUsize.html#method.fetch_max
             let reset_lines = RESET_LINES.fetch_max(lines, Ordering::SeqCst).max(lines);
             print!("{}\n\u{001B}[{}A", str, reset_lines);
-            std::thread::sleep(delay);
+            std::thread::sleep(delay.mul_f64(f64::from_bits(FRAME_DELAY.load(Ordering::SeqCst))));
         }
     }
 
     pub fn clear_interactive() {
         let lines = RESET_LINES.swap(0, Ordering::SeqCst);
-        print!("\u{001B}[{}B", lines);
+        if lines > 0 {
+            print!("\u{001B}[{}B", lines);
+        }
+    }
+
+    /// Clears the terminal and moves the cursor to the top-left, so output can be redrawn from
+    /// scratch.
+    pub fn clear_screen() {
+        RESET_LINES.store(0, Ordering::SeqCst);
+        print!("\u{001B}[2J\u{001B}[H");
     }
 }
 
 // Take advantage of Drop to (attempt to) unconditionally restore the cursor. See
 // https://stackoverflow.com/a/57860708/113632 for more, or
 // https://doc.rust-lang.org/std/panic/fn.catch_unwind.html for another potential approach.
-#[cfg(any(feature="interactive", all(debug_assertions, not(test))))]
 impl Drop for Console {
     fn drop(&mut self) {
-        print!("\u{001B}[?25h"); // restore cursor
+        GUARDED.store(false, Ordering::SeqCst);
+        if CURSOR_HIDDEN.swap(false, Ordering::SeqCst) {
+            print!("\u{001B}[?25h"); // restore cursor
+        }
     }
 }
@@ -0,0 +1,163 @@
+pub use crate::generate::Rng;
+use crate::solution::{Answer, Solution};
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+type Implementation<I, O> = Box<dyn Fn(&I) -> O>;
+
+/// Runs several implementations of the same function against generated inputs, and reports the
+/// smallest input they disagree on.
+pub struct Differential<I, O> {
+    name: &'static str,
+    implementations: Vec<(&'static str, Implementation<I, O>)>,
+}
+
+impl<I: Debug, O: Debug + PartialEq> Differential<I, O> {
+    pub fn new(name: &'static str) -> Differential<I, O> {
+        Differential{ name, implementations: Vec::new() }
+    }
+
+    pub fn implementation(mut self, name: &'static str, f: impl Fn(&I) -> O + 'static) -> Differential<I, O> {
+        self.implementations.push((name, Box::new(f)));
+        self
+    }
+
+    // Each implementation's output, if they don't all agree
+    fn disagreement(&self, input: &I) -> Option<Vec<(&'static str, O)>> {
+        let outputs: Vec<_> = self.implementations.iter().map(|(name, f)| (*name, f(input))).collect();
+        if outputs.windows(2).all(|w| w[0].1 == w[1].1) { None } else { Some(outputs) }
+    }
+
+    /// Checks the implementations agree on `cases` inputs created by `generate`. If they don't, the
+    /// input is repeatedly replaced by the first of its `shrink` candidates that still causes a
+    /// disagreement, and the result is returned as the error. Shrinking must only produce inputs
+    /// `generate` could have.
+    pub fn run(&self, cases: usize, seed: u64, generate: impl Fn(&mut Rng) -> I, shrink: impl Fn(&I) -> Vec<I>)
+            -> Result<(), String> {
+        assert!(self.implementations.len() >= 2, "{} needs at least two implementations to compare", self.name);
+        let mut rng = Rng::new(seed);
+        for case in 0..cases {
+            let input = generate(&mut rng);
+            if let Some(outputs) = self.disagreement(&input) {
+                let (input, outputs, steps) = self.minimize(input, outputs, &shrink);
+                let mut message = format!("{} implementations disagree on case {} (seed {}, shrunk {} times):\n  input: {:?}",
+                                          self.name, case, seed, steps, input);
+                for (name, output) in outputs {
+                    message.push_str(&format!("\n  {}: {:?}", name, output));
+                }
+                return Err(message);
+            }
+        }
+        Ok(())
+    }
+
+    /// Panics with the minimized counterexample if the implementations disagree.
+    pub fn check(&self, cases: usize, seed: u64, generate: impl Fn(&mut Rng) -> I, shrink: impl Fn(&I) -> Vec<I>) {
+        if let Err(message) = self.run(cases, seed, generate, shrink) {
+            panic!("{}", message);
+        }
+    }
+
+    fn minimize(&self, mut input: I, mut outputs: Vec<(&'static str, O)>, shrink: impl Fn(&I) -> Vec<I>)
+            -> (I, Vec<(&'static str, O)>, usize) {
+        let mut steps = 0;
+        'shrinking: loop {
+            for candidate in shrink(&input) {
+                if let Some(candidate_outputs) = self.disagreement(&candidate) {
+                    input = candidate;
+                    outputs = candidate_outputs;
+                    steps += 1;
+                    continue 'shrinking;
+                }
+            }
+            return (input, outputs, steps);
+        }
+    }
+}
+
+/// Shrink candidates for a list: each with one element removed, as long as at least `min_len`
+/// remain.
+pub fn remove_one<T: Clone>(list: &[T], min_len: usize) -> Vec<Vec<T>> {
+    if list.len() <= min_len { return Vec::new(); }
+    (0..list.len()).map(|i| list.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, v)| v.clone()).collect()).collect()
+}
+
+/// Checks a day's Solution gets the answers its generator built into `cases` inputs, with sizes in
+/// `sizes`. Panics with the seed, size and generated input of the first case it gets wrong; these
+/// aren't shrunk, since a smaller seed or size generates an unrelated input.
+pub fn check_generator<S: Solution>(name: &'static str, cases: usize, sizes: RangeInclusive<usize>) {
+    fn solve<S: Solution>(input: &str) -> anyhow::Result<(Answer, Answer)> {
+        let solution = S::parse(input)?;
+        Ok((solution.part1()?, solution.part2()?))
+    }
+
+    let mut rng = Rng::new(1);
+    for case in 0..cases {
+        let (seed, size) = (rng.next_u64(), rng.range(*sizes.start() as u64, *sizes.end() as u64 + 1) as usize);
+        let generated = S::generate(&mut Rng::new(seed), size)
+            .unwrap_or_else(|e| panic!("{} case {} (seed {}, size {}) failed to generate: {:#}", name, case, seed, size, e));
+        let expected = format!("{}, {}", generated.part1, generated.part2);
+        let actual = match solve::<S>(&generated.input) {
+            Ok((part1, part2)) => format!("{}, {}", part1, part2),
+            Err(e) => format!("{:#}", e),
+        };
+        if actual != expected {
+            panic!("{} solution disagrees with the generator on case {} (seed {}, size {}):\n  input:\n{}  generator: {}\n  solution: {}",
+                   name, case, seed, size, generated.input, expected, actual);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::Generated;
+
+    fn generate(rng: &mut Rng) -> Vec<u64> {
+        let len = rng.range(5, 20) as usize;
+        (0..len).map(|_| rng.range(0, 10)).collect()
+    }
+
+    #[test]
+    fn agree() {
+        Differential::new("sum")
+            .implementation("iter", |v: &Vec<u64>| v.iter().sum::<u64>())
+            .implementation("loop", |v: &Vec<u64>| {
+                let mut total = 0;
+                for n in v { total += n; }
+                total
+            })
+            .check(50, 1, generate, |v| remove_one(v, 0));
+    }
+
+    #[test]
+    fn minimizes() {
+        // Off by one whenever there's a 7
+        let error = Differential::new("sum")
+            .implementation("correct", |v: &Vec<u64>| v.iter().sum::<u64>())
+            .implementation("buggy", |v: &Vec<u64>| v.iter().map(|&n| if n == 7 { 8 } else { n }).sum::<u64>())
+            .run(50, 1, generate, |v| remove_one(v, 0)).unwrap_err();
+        assert!(error.starts_with("sum implementations disagree on case "), "{}", error);
+        assert!(error.ends_with("\n  input: [7]\n  correct: 7\n  buggy: 8"), "{}", error);
+    }
+
+    // Always answers 1 and 2, but generates inputs it claims answer 3 and 2
+    struct Miscounter;
+
+    impl Solution for Miscounter {
+        fn parse(_input: &str) -> anyhow::Result<Self> { Ok(Miscounter) }
+
+        fn generate(_rng: &mut Rng, size: usize) -> anyhow::Result<Generated> {
+            Ok(Generated{ input: "x\n".repeat(size), part1: 3.into(), part2: 2.into() })
+        }
+
+        fn part1(&self) -> anyhow::Result<Answer> { Ok(1.into()) }
+        fn part2(&self) -> anyhow::Result<Answer> { Ok(2.into()) }
+    }
+
+    #[test]
+    #[should_panic(expected = "on case 0 (seed 973819730272012410, size 2):\n  input:\nx\nx\n  generator: 3, 2\n  solution: 1, 2")]
+    fn generator_mismatch() {
+        check_generator::<Miscounter>("miscounter", 5, 2..=2);
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable holding the adventofcode.com session cookie.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+/// Environment variable overriding where inputs are downloaded from.
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+
+/// Retrieves a day's puzzle input from somewhere other than the local disk.
+pub trait Fetcher {
+    fn fetch(&self, day: u32) -> Result<String>;
+}
+
+/// Downloads inputs from `BASE_URL/2020/day/N/input`, authenticating with a session token.
+pub struct HttpFetcher {
+    base_url: String,
+    session: Option<String>,
+}
+
+impl HttpFetcher {
+    pub const DEFAULT_BASE_URL: &'static str = "https://adventofcode.com";
+
+    pub fn new(base_url: &str, session: Option<&str>) -> HttpFetcher {
+        HttpFetcher{ base_url: base_url.trim_end_matches('/').to_string(), session: session.map(|s| s.to_string()) }
+    }
+
+    /// Reads the session token and (optionally) base URL from the environment.
+    pub fn from_env() -> HttpFetcher {
+        let base_url = std::env::var(BASE_URL_VAR).unwrap_or_else(|_| HttpFetcher::DEFAULT_BASE_URL.to_string());
+        HttpFetcher::new(&base_url, std::env::var(SESSION_VAR).ok().as_deref())
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, day: u32) -> Result<String> {
+        let session = self.session.as_ref()
+            .ok_or_else(|| anyhow!("No session token; set {} to download inputs", SESSION_VAR))?;
+        let url = format!("{}/2020/day/{}/input", self.base_url, day);
+        let response = ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build()
+            .get(&url)
+            .set("Cookie", &format!("session={}", session))
+            .set("User-Agent", concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+            .call();
+        match response {
+            Ok(response) => response.into_string().with_context(|| format!("Failed to read response from {}", url)),
+            Err(ureq::Error::Status(code, response)) =>
+                Err(anyhow!("GET {} returned {} {}", url, code, response.status_text())),
+            Err(e) => Err(e.into()), // already describes the URL
+        }
+    }
+}
+
+/// Serves inputs from a directory, fetching and storing any that aren't there yet. Cached inputs
+/// are never fetched again; empty files are placeholders, not inputs, and are replaced.
+pub struct Cache<F> {
+    dir: PathBuf,
+    fetcher: F,
+}
+
+impl<F: Fetcher> Cache<F> {
+    pub fn new(dir: &Path, fetcher: F) -> Cache<F> {
+        Cache{ dir: dir.to_path_buf(), fetcher }
+    }
+
+    pub fn path(&self, day: u32) -> PathBuf {
+        self.dir.join(format!("day{:02}.txt", day))
+    }
+
+    pub fn load(&self, day: u32) -> Result<String> {
+        let path = self.path(day);
+        if path.metadata().map(|m| m.len() > 0).unwrap_or(false) {
+            return std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read input {}", path.display()));
+        }
+        let input = self.fetcher.fetch(day)
+            .with_context(|| format!("{} not found, and failed to fetch it", path.display()))?;
+        self.store(&path, &input)?;
+        Ok(input)
+    }
+
+    // Writes to a temporary file and renames it into place, so an interrupted download never
+    // leaves a partial input behind to be mistaken for a cached one.
+    fn store(&self, path: &Path, input: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let file_name = path.file_name().expect("Always set by path()").to_string_lossy();
+        let temp = self.dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+        std::fs::write(&temp, input).with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    // Stands in for adventofcode.com on a loopback port, answering every request with the same
+    // response and recording the request line and headers it received.
+    struct StandIn {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl StandIn {
+        fn start(status: &'static str, body: &'static str) -> StandIn {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let log = requests.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut request = String::new();
+                    for line in BufReader::new(stream.try_clone().unwrap()).lines() {
+                        let line = line.unwrap();
+                        if line.is_empty() { break; }
+                        request.push_str(&line);
+                        request.push('\n');
+                    }
+                    log.lock().unwrap().push(request);
+                    write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                           status, body.len(), body).unwrap();
+                }
+            });
+            StandIn{ url, requests }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("advent-2020-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files: Vec<_> = std::fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn fetch_and_cache() {
+        let server = StandIn::start("200 OK", "1\n2\n3\n");
+        let dir = temp_dir("fetch-and-cache");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("day05.txt"), "").unwrap(); // placeholder
+        let cache = Cache::new(&dir, HttpFetcher::new(&format!("{}/", server.url), Some("secret")));
+
+        assert_eq!(cache.load(5).unwrap(), "1\n2\n3\n");
+        assert_eq!(cache.load(5).unwrap(), "1\n2\n3\n");
+        assert_eq!(files(&dir), vec!("day05.txt"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1, "Should only fetch once: {:?}", requests);
+        assert!(requests[0].starts_with("GET /2020/day/5/input HTTP/1.1\n"), "{}", requests[0]);
+        assert!(requests[0].to_lowercase().contains("cookie: session=secret\n"), "{}", requests[0]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cached_days_are_not_fetched() {
+        let server = StandIn::start("200 OK", "fetched");
+        let dir = temp_dir("cached");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("day12.txt"), "cached").unwrap();
+        let cache = Cache::new(&dir, HttpFetcher::new(&server.url, Some("secret")));
+
+        assert_eq!(cache.load(12).unwrap(), "cached");
+        assert!(server.requests().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_fetch() {
+        let server = StandIn::start("400 Bad Request", "Please log in");
+        let dir = temp_dir("failed-fetch");
+        let cache = Cache::new(&dir, HttpFetcher::new(&server.url, Some("expired")));
+
+        let error = format!("{:#}", cache.load(1).unwrap_err());
+        assert!(error.contains("400 Bad Request"), "{}", error);
+        assert!(!dir.exists() || files(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn no_session() {
+        let server = StandIn::start("200 OK", "unused");
+        let error = HttpFetcher::new(&server.url, None).fetch(1).unwrap_err();
+        assert!(error.to_string().contains(SESSION_VAR));
+        assert!(server.requests().is_empty());
+    }
+}
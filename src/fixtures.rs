@@ -0,0 +1,144 @@
+use crate::solution::Day;
+use anyhow::{bail, ensure, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// An example input, `dayNN_example*.txt`, and the answers it should produce.
+///
+/// The answers are read from a sidecar `dayNN_example*.answers` file. Each non-blank, non-comment
+/// line is `PART ANSWER`, or `options ARGS...` to pass options to the day. Parts without an answer
+/// aren't checked, since not every example applies to both parts; a sidecar with no answers (e.g.
+/// for a day that isn't solved yet) only checks that the example parses.
+#[derive(Debug, PartialEq)]
+pub struct Fixture {
+    pub day: u32,
+    pub input: PathBuf,
+    pub args: Vec<String>,
+    pub answers: BTreeMap<u32, String>,
+}
+
+/// Finds every example in `dir`, in name order. Examples without a sidecar are an error, so they
+/// can't silently go unchecked.
+pub fn discover(dir: &Path) -> Result<Vec<Fixture>> {
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let day = match name.strip_suffix(".txt").and_then(|n| n.strip_prefix("day")).and_then(|n| n.split_once("_example")) {
+            Some((day, _)) => day.parse().with_context(|| format!("Invalid day: {}", name))?,
+            None => continue,
+        };
+        let sidecar = path.with_extension("answers");
+        let text = std::fs::read_to_string(&sidecar).with_context(|| format!("Failed to read {}", sidecar.display()))?;
+        let (args, answers) = parse_sidecar(&text).with_context(|| format!("Invalid {}", sidecar.display()))?;
+        fixtures.push(Fixture{ day, input: path, args, answers });
+    }
+    fixtures.sort_by(|a, b| a.input.cmp(&b.input));
+    Ok(fixtures)
+}
+
+pub fn parse_sidecar(text: &str) -> Result<(Vec<String>, BTreeMap<u32, String>)> {
+    let mut args = Vec::new();
+    let mut answers = BTreeMap::new();
+    for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (key, value) = line.split_once(char::is_whitespace).with_context(|| format!("Expected PART ANSWER: {}", line))?;
+        match key {
+            "options" => args.extend(value.split_whitespace().map(|a| a.to_string())),
+            "1" | "2" => ensure!(answers.insert(key.parse()?, value.trim().to_string()).is_none(), "Part {} is repeated", key),
+            _ => bail!("Expected PART ANSWER or options ARGS: {}", line),
+        }
+    }
+    Ok((args, answers))
+}
+
+impl Fixture {
+    /// The example's file name, e.g. `day07_example1.txt`.
+    pub fn name(&self) -> String {
+        self.input.file_name().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    /// Solves the example through the day's Solution, describing each answer that doesn't match.
+    pub fn check(&self, days: &[Day]) -> Vec<String> {
+        let name = self.name();
+        let solution = days.iter().find(|d| d.number == self.day).context("No such day")
+            .and_then(|day| {
+                let input = std::fs::read_to_string(&self.input)?;
+                day.load(&input, &self.args)
+            });
+        let solution = match solution {
+            Ok(solution) => solution,
+            Err(e) => return vec!(format!("{}: {:#}", name, e)),
+        };
+        self.answers.iter().filter_map(|(&part, expected)| {
+            let answer = if part == 1 { solution.part1() } else { solution.part2() };
+            match answer {
+                Ok(answer) if answer.to_string() == *expected => None,
+                Ok(answer) => Some(format!("{} part {}: expected {}, got {}", name, part, expected, answer)),
+                Err(e) => Some(format!("{} part {}: {:#}", name, part, e)),
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_fixtures() -> Vec<Fixture> {
+        discover(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data")).unwrap()
+    }
+
+    fn check_example(name: &str) {
+        let fixture = data_fixtures().into_iter().find(|f| f.name() == name).expect("Example was removed");
+        let failures = fixture.check(crate::DAYS);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    // One test per example in data/, named after it (e.g. examples::day07_example1), generated by
+    // build.rs
+    mod examples {
+        include!(concat!(env!("OUT_DIR"), "/fixture_tests.rs"));
+    }
+
+    // Catches examples build.rs didn't generate a test for, e.g. if its file name filter drifts
+    #[test]
+    fn every_example_has_a_test() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/fixture_tests.rs"));
+        let fixtures = data_fixtures();
+        assert!(!fixtures.is_empty());
+        for fixture in fixtures {
+            assert!(generated.contains(&format!("check_example({:?})", fixture.name())), "{}", fixture.name());
+        }
+    }
+
+    #[test]
+    fn sidecars() {
+        let (args, answers) = parse_sidecar("# comment\n\n1 37\noptions --rounds 10\n2 a,b\n").unwrap();
+        assert_eq!(args, vec!("--rounds", "10"));
+        assert_eq!(answers, vec!((1, "37".to_string()), (2, "a,b".to_string())).into_iter().collect());
+
+        let (args, answers) = parse_sidecar("# Not solved yet\n").unwrap();
+        assert!(args.is_empty() && answers.is_empty());
+        assert!(parse_sidecar("3 5").is_err());
+        assert!(parse_sidecar("1").is_err());
+        assert!(parse_sidecar("1 5\n1 6").is_err());
+    }
+
+    #[test]
+    fn discovery() {
+        let dir = std::env::temp_dir().join(format!("advent-2020-fixtures-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in &[("day01.txt", ""), ("day01_example.txt", ""), ("day01_example.answers", "1 514579"),
+                                  ("day07_example2.txt", ""), ("day07_example2.answers", "2 126")] {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+        let days: Vec<_> = discover(&dir).unwrap().iter().map(|f| (f.day, f.answers.clone())).collect();
+        assert_eq!(days, vec!((1, vec!((1, "514579".to_string())).into_iter().collect()),
+                              (7, vec!((2, "126".to_string())).into_iter().collect())));
+
+        std::fs::write(dir.join("day03_example.txt"), "").unwrap();
+        assert!(format!("{:#}", discover(&dir).unwrap_err()).contains("day03_example.answers"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,81 @@
+use crate::solution::Answer;
+
+/// A small, seedable xorshift64* generator, so generated inputs are reproducible.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck at zero; mix the seed so nearby seeds diverge immediately
+        Rng(seed.wrapping_mul(0x9E3779B97F4A7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// A number in `[low, high)`.
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high, "Empty range {}..{}", low, high);
+        low + self.next_u64() % (high - low)
+    }
+
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.range(0, denominator) < numerator
+    }
+
+    /// Picks `count` distinct numbers from `[low, high)`, in random order.
+    pub fn distinct(&mut self, count: usize, low: u64, high: u64) -> Vec<u64> {
+        assert!(count as u64 <= high - low, "Can't pick {} distinct numbers from {}..{}", count, low, high);
+        let mut picked = Vec::with_capacity(count);
+        while picked.len() < count {
+            let n = self.range(low, high);
+            if !picked.contains(&n) { picked.push(n); }
+        }
+        picked
+    }
+
+    /// Shuffles `list` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, list: &mut [T]) {
+        for i in (1..list.len()).rev() {
+            list.swap(i, self.range(0, i as u64 + 1) as usize);
+        }
+    }
+}
+
+/// A randomly generated puzzle input, along with the answers it was constructed to have.
+#[derive(Debug)]
+pub struct Generated {
+    pub input: String,
+    pub part1: Answer,
+    pub part2: Answer,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng() {
+        let (mut a, mut b) = (Rng::new(1), Rng::new(1));
+        let values: Vec<_> = (0..100).map(|_| a.range(5, 10)).collect();
+        assert_eq!(values, (0..100).map(|_| b.range(5, 10)).collect::<Vec<_>>());
+        assert!(values.iter().all(|v| (5..10).contains(v)));
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+        let mut distinct = a.distinct(5, 0, 5);
+        distinct.sort();
+        assert_eq!(distinct, vec!(0, 1, 2, 3, 4));
+    }
+
+    #[test]
+    fn shuffle() {
+        let mut list: Vec<_> = (0..20).collect();
+        Rng::new(1).shuffle(&mut list);
+        assert_ne!(list, (0..20).collect::<Vec<_>>());
+        list.sort();
+        assert_eq!(list, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::input;
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Where the expected output for `day` is checked in.
+pub fn path(day: u32) -> PathBuf {
+    input::data_dir().join(format!("day{:02}.expected", day))
+}
+
+/// Runs this binary on `day`'s real input, with rendering, caching and the config file disabled
+/// and each part's details enabled, and returns what it printed to stdout.
+pub fn capture(day: u32) -> Result<String> {
+    let exe = std::env::current_exe().context("Failed to find the running binary")?;
+    let output = Command::new(&exe)
+        .args(["--no-config", "--no-interactive", "--no-cache", "--data-dir"])
+        .arg(input::data_dir())
+        .arg("--verbose")
+        .arg(day.to_string())
+        .output()
+        .with_context(|| format!("Failed to run {}", exe.display()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("Exited with {}: {}", output.status, stderr.trim());
+    }
+    Ok(normalize(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Drops the elapsed!() lines the timing feature adds, since they differ on every run
+fn normalize(stdout: &str) -> String {
+    stdout.lines().filter(|l| !l.starts_with("\u{001B}[36m")).map(|l| format!("{}\n", l)).collect()
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Status {
+    Pass,
+    Updated,
+    Missing,
+    Differs{ line: usize, expected: String, actual: String },
+    Error(String),
+}
+
+impl Status {
+    pub fn passed(&self) -> bool {
+        matches!(self, Status::Pass | Status::Updated)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Updated => write!(f, "updated"),
+            Status::Missing => write!(f, "MISSING no expected output; run with --update to create it"),
+            Status::Differs{ line, expected, actual } =>
+                write!(f, "FAIL line {} expected {:?}, got {:?}", line, expected, actual),
+            Status::Error(e) => write!(f, "ERROR {}", e),
+        }
+    }
+}
+
+/// Compares the output with the expected output, reporting the first line that differs.
+pub fn compare(expected: &str, actual: &str) -> Status {
+    let (mut expected_lines, mut actual_lines) = (expected.lines(), actual.lines());
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e == a => {},
+            (e, a) => return Status::Differs{
+                line,
+                expected: e.unwrap_or("<end of output>").to_string(),
+                actual: a.unwrap_or("<end of output>").to_string(),
+            },
+        }
+    }
+    Status::Pass
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_timing() {
+        let stdout = "\u{001B}[36m[Part 1...\u{001B}[0m\n\u{001B}[36mElapsed: 1ms\u{001B}[0m\nPart 1: 5\nPart 2: 6";
+        assert_eq!(normalize(stdout), "Part 1: 5\nPart 2: 6\n");
+    }
+
+    parameterized_test::create!{comparisons, (actual, status), {
+        assert_eq!(compare("Part 1: 5\nPart 2: 6\n", actual), status);
+    }}
+    comparisons!{
+        same: ("Part 1: 5\nPart 2: 6\n", Status::Pass),
+        changed: ("Part 1: 5\nPart 2: 7\n", Status::Differs{ line: 2, expected: "Part 2: 6".into(), actual: "Part 2: 7".into() }),
+        shorter: ("Part 1: 5\n", Status::Differs{ line: 2, expected: "Part 2: 6".into(), actual: "<end of output>".into() }),
+        longer: ("Part 1: 5\nPart 2: 6\nextra\n", Status::Differs{ line: 3, expected: "<end of output>".into(), actual: "extra".into() }),
+    }
+}
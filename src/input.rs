@@ -0,0 +1,132 @@
+use crate::fetch::{Cache, HttpFetcher};
+use crate::solution::Failure;
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where a day's puzzle input should be read from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Source {
+    /// data/dayNN.txt, or the copy baked into the binary with the `embedded` feature. Missing
+    /// inputs are downloaded and saved there if a session token is configured.
+    Default,
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    /// Interprets a command-line argument; `-` means stdin.
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" { Source::Stdin } else { Source::File(PathBuf::from(arg)) }
+    }
+}
+
+/// Reads the day's input; any error is a Failure::bad_input().
+pub fn load(day: u32, source: &Source) -> Result<String> {
+    load_from(day, source).map_err(Failure::bad_input)
+}
+
+fn load_from(day: u32, source: &Source) -> Result<String> {
+    match source {
+        Source::Default => load_default(day),
+        Source::Stdin => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).context("Failed to read input from stdin")?;
+            Ok(input)
+        },
+        Source::File(path) => read(path),
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read input {}", path.display()))
+}
+
+lazy_static! {
+    static ref DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Reads inputs from, and downloads them to, `dir` instead of the data directory.
+pub fn set_data_dir(dir: &Path) {
+    *DATA_DIR.lock().unwrap() = Some(dir.to_path_buf());
+}
+
+/// Where day inputs and examples live.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = DATA_DIR.lock().unwrap().as_ref() {
+        return dir.clone();
+    }
+    let relative = PathBuf::from("data");
+    if relative.is_dir() {
+        return relative;
+    }
+    // Fall back to the checkout's data directory so the binary can be invoked from anywhere
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+/// The data directory, which doubles as a cache of downloaded inputs.
+pub fn cache() -> Cache<HttpFetcher> {
+    Cache::new(&data_dir(), HttpFetcher::from_env())
+}
+
+#[cfg(not(feature="embedded"))]
+fn load_default(day: u32) -> Result<String> {
+    cache().load(day)
+}
+
+#[cfg(feature="embedded")]
+fn load_default(day: u32) -> Result<String> {
+    match embedded::input(day) {
+        Some(input) => Ok(input.to_string()),
+        None => cache().load(day),
+    }
+}
+
+#[cfg(feature="embedded")]
+mod embedded {
+    macro_rules! embed {
+        ($day:expr, $($n:literal => $file:literal),* $(,)?) => {
+            match $day {
+                $($n => Some(include_str!(concat!("../data/", $file))),)*
+                _ => None,
+            }
+        };
+    }
+
+    pub fn input(day: u32) -> Option<&'static str> {
+        embed!(day,
+            1 => "day01.txt", 2 => "day02.txt", 3 => "day03.txt", 4 => "day04.txt",
+            5 => "day05.txt", 6 => "day06.txt", 7 => "day07.txt", 8 => "day08.txt",
+            9 => "day09.txt", 10 => "day10.txt", 11 => "day11.txt", 12 => "day12.txt",
+            13 => "day13.txt", 14 => "day14.txt", 15 => "day15.txt", 16 => "day16.txt",
+            17 => "day17.txt", 18 => "day18.txt", 19 => "day19.txt", 20 => "day20.txt",
+            21 => "day21.txt", 22 => "day22.txt", 23 => "day23.txt", 24 => "day24.txt",
+            25 => "day25.txt",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_arg() {
+        assert_eq!(Source::from_arg("-"), Source::Stdin);
+        assert_eq!(Source::from_arg("foo.txt"), Source::File(PathBuf::from("foo.txt")));
+    }
+
+    #[test]
+    fn default() {
+        assert!(cache().path(1).ends_with("data/day01.txt"));
+        assert_eq!(load(1, &Source::Default).unwrap(), include_str!("../data/day01.txt"));
+    }
+
+    #[test]
+    fn file() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/day03_example.txt");
+        assert_eq!(load(3, &Source::File(path)).unwrap(), include_str!("../data/day03_example.txt"));
+        assert!(load(3, &Source::from_arg("no/such/file.txt")).is_err());
+    }
+}
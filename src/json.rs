@@ -0,0 +1,121 @@
+use std::fmt;
+
+/// A minimal JSON value, just enough to emit machine-readable output without pulling in serde.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// Builds a Value::Object from `key => value` pairs, converting each value with Value::from.
+macro_rules! json_object {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        crate::json::Value::Object(vec!($(($key.to_string(), crate::json::Value::from($value)),)*))
+    };
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Value { Value::Int(n) }
+}
+
+impl From<u32> for Value {
+    fn from(n: u32) -> Value { Value::Int(n as i64) }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Value { Value::Int(n as i64) }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Value { Value::Float(n) }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value { Value::Bool(b) }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value { Value::Str(s.to_string()) }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value { Value::Str(s) }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(o: Option<T>) -> Value { o.map(|v| v.into()).unwrap_or(Value::Null) }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Value { Value::Array(v.into_iter().map(|e| e.into()).collect()) }
+}
+
+fn write_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(n) if n.is_finite() => write!(f, "{}", n),
+            Value::Float(_) => write!(f, "null"),
+            Value::Str(s) => write_str(f, s),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            },
+            Value::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    parameterized_test::create!{display, (value, expected), {
+        assert_eq!(value.to_string(), expected);
+    }}
+    display!{
+        null: (Value::Null, "null"),
+        int: (Value::from(-5_i64), "-5"),
+        float: (Value::from(1.5), "1.5"),
+        nan: (Value::from(f64::NAN), "null"),
+        string: (Value::from("a \"b\"\n\\c\u{1}"), r#""a \"b\"\n\\c\u0001""#),
+        option: (Value::from(None::<i64>), "null"),
+        array: (Value::from(vec!(1_i64, 2, 3)), "[1,2,3]"),
+        object: (json_object!("day" => 1_u32, "answer" => "x", "ok" => true), r#"{"day":1,"answer":"x","ok":true}"#),
+    }
+}
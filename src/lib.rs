@@ -0,0 +1,88 @@
+//! Advent of Code 2020 solutions, along with the helpers they share: 2D geometry in `euclid`,
+//! regex-based parsing (and the `static_regex!` macro) in `parsing`, the handheld console's
+//! interpreter in `machine`, and terminal rendering (and the `elapsed!` macro) in `console`.
+//! The `advent-2020` binary is a thin wrapper around `cli`.
+
+// https://github.com/rust-lang/cargo/issues/3591#issuecomment-475701083
+//#![ allow( dead_code, unused_imports, unused_macros, unused_variables ) ]
+#[macro_use] extern crate lazy_static;
+extern crate parameterized_test;
+extern crate regex;
+extern crate anyhow;
+
+// Used by the exported macros, so crates using them don't need their own dependencies
+#[doc(hidden)]
+pub mod __private {
+    pub use lazy_static::lazy_static;
+    pub use regex::Regex;
+}
+
+#[macro_use] pub mod console;
+#[macro_use] pub mod parsing;
+#[macro_use] mod json;
+#[macro_use] mod trace;
+mod batch;
+mod answer_cache;
+mod bench;
+mod budget;
+pub mod cli;
+mod config;
+#[cfg(test)] mod differential;
+pub mod euclid;
+mod fetch;
+mod generate;
+#[cfg(test)] mod fixtures;
+mod golden;
+mod input;
+pub mod machine;
+pub mod memory;
+mod options;
+mod report;
+mod runner;
+mod scaffold;
+
+mod solution;
+mod verify;
+mod watch;
+
+use solution::Day;
+
+// Declares each day's module and registers its Solution in DAYS
+macro_rules! days {
+    ($($day:literal => $module:ident::$solution:ident),* $(,)?) => {
+        $(mod $module;)*
+        static DAYS: &[Day] = &[
+            $(Day{ number: $day, parse: solution::parse::<$module::$solution>,
+                   options: <$module::$solution as solution::Solution>::options,
+                   generate: <$module::$solution as solution::Solution>::generate },)*
+        ];
+    };
+}
+
+days! {
+    1 => aoc01::Expenses,
+    2 => aoc02::Passwords,
+    3 => aoc03::Landscape,
+    4 => aoc04::Passports,
+    5 => aoc05::BoardingPasses,
+    6 => aoc06::Groups,
+    7 => aoc07::BagRules,
+    8 => aoc08::BootCode,
+    9 => aoc09::Xmas,
+    10 => aoc10::Adapters,
+    11 => aoc11::Floor,
+    12 => aoc12::Navigation,
+    13 => aoc13::Schedule,
+    14 => aoc14::DockingProgram,
+    15 => aoc15::MemoryGame,
+    16 => aoc16::TicketData,
+    17 => aoc17::PocketDimension,
+    18 => aoc18::Homework,
+    19 => aoc19::Messages,
+    20 => aoc20::Pieces,
+    21 => aoc21::Foods,
+    22 => aoc22::Combat,
+    23 => aoc23::CupGame,
+    24 => aoc24::Lobby,
+    25 => aoc25::Handshake,
+}
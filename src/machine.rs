@@ -1,7 +1,9 @@
+use crate::budget;
 use crate::parsing;
-use anyhow::{bail, Error, Result};
+use anyhow::{bail, Context, Error, Result};
 use std::str::FromStr;
 use std::collections::HashSet;
+use std::convert::TryFrom;
 
 #[derive(Debug, Copy, Clone)]
 pub enum Instruction {
@@ -42,6 +44,7 @@ impl FromStr for Program {
     }
 }
 
+#[derive(Default)]
 pub struct Machine {
     acc: i32,
 }
@@ -55,19 +58,33 @@ impl Machine {
         self.acc
     }
 
-    pub fn run_until_complete(&mut self, program: &Program) -> bool {
+    /// Runs the program until it completes (true) or is about to execute an instruction a second
+    /// time (false). Jumping anywhere other than an instruction or just past the end is an error.
+    pub fn run_until_complete(&mut self, program: &Program) -> Result<bool> {
         let mut counter: i32 = 0;
         let mut seen = HashSet::new();
         loop {
-            if !seen.insert(counter) { return false; }
-            if counter == program.commands.len() as i32 { return true; }
-            let command = &program.commands[counter as usize];
-            match command {
-                Instruction::ACC(value) => self.acc += value,
-                Instruction::JMP(value) => counter += value -1,
-                Instruction::NOP(_) => {},
+            if seen.len() % 1024 == 0 {
+                budget::check().with_context(|| format!("Executed {} instructions", seen.len()))?;
             }
-            counter = counter+1; // TODO handle overflow?
+            if !seen.insert(counter) { return Ok(false); }
+            if counter == program.commands.len() as i32 { return Ok(true); }
+            let command = match usize::try_from(counter).ok().and_then(|c| program.commands.get(c)) {
+                Some(command) => command,
+                None => bail!("Jumped outside the program, to instruction {}", counter),
+            };
+            let offset = match command {
+                Instruction::ACC(value) => {
+                    self.acc = self.acc.checked_add(*value).context("Accumulator overflowed")?;
+                    1
+                },
+                Instruction::JMP(value) => *value,
+                Instruction::NOP(_) => 1,
+            };
+            counter = match counter.checked_add(offset) {
+                Some(counter) => counter,
+                None => bail!("Jumped outside the program, from instruction {} by {}", counter, offset),
+            };
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+#[cfg(feature="timing")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Whether allocations are being counted, i.e. whether Counting is the global allocator.
+pub const ENABLED: bool = cfg!(feature="timing");
+
+#[cfg(feature="timing")]
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// These are process-wide, so measurements of concurrently-running days (--jobs) include each other
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Delegates to the system allocator, counting allocations and how many bytes are live.
+#[cfg(feature="timing")]
+pub struct Counting;
+
+#[cfg(feature="timing")]
+impl Counting {
+    fn allocated(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(size, Ordering::Relaxed);
+        let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn freed(size: usize) {
+        LIVE.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature="timing")]
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() { Counting::allocated(layout.size()); }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Counting::freed(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() { Counting::allocated(layout.size()); }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Counting::freed(layout.size());
+            Counting::allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// The allocations made while a Scope was active.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Usage {
+    pub allocations: usize,
+    pub bytes: usize,
+    /// The most bytes live at once, beyond those already live when the scope started
+    pub peak: usize,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} allocs, {} total, {} peak", self.allocations, format_bytes(self.bytes), format_bytes(self.peak))
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len()-1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+/// Tracks allocations from when it's started until usage() is called. Scopes can be nested.
+pub struct Scope {
+    allocations: usize,
+    bytes: usize,
+    live: usize,
+    outer_peak: usize,
+}
+
+impl Scope {
+    pub fn start() -> Scope {
+        let live = LIVE.load(Ordering::Relaxed);
+        // Restart peak tracking from the current live bytes, and restore the outer peak on drop
+        let outer_peak = PEAK.swap(live, Ordering::Relaxed);
+        Scope{ allocations: ALLOCATIONS.load(Ordering::Relaxed), bytes: BYTES.load(Ordering::Relaxed), live, outer_peak }
+    }
+
+    pub fn usage(&self) -> Usage {
+        Usage{
+            allocations: ALLOCATIONS.load(Ordering::Relaxed) - self.allocations,
+            bytes: BYTES.load(Ordering::Relaxed) - self.bytes,
+            peak: PEAK.load(Ordering::Relaxed).saturating_sub(self.live),
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    parameterized_test::create!{bytes, (n, expected), {
+        assert_eq!(format_bytes(n), expected);
+    }}
+    bytes!{
+        small: (512, "512B"),
+        kib: (1536, "1.5KiB"),
+        mib: (30_000_000 * 16, "457.8MiB"),
+        gib: (28 * 1024 * 1024 * 1024, "28.0GiB"),
+    }
+
+    // Only meaningful when the counting allocator is installed
+    #[cfg(feature="timing")]
+    #[test]
+    fn nested_scopes() {
+        let outer = Scope::start();
+        let big = vec![0_u8; 1 << 20];
+        drop(big);
+        let inner = Scope::start();
+        let small = vec![0_u8; 1 << 10];
+        let inner_usage = inner.usage();
+        drop(inner);
+        drop(small);
+        let outer_usage = outer.usage();
+
+        assert!(inner_usage.allocations >= 1 && inner_usage.peak >= 1 << 10 && inner_usage.peak < 1 << 20,
+                "{:?}", inner_usage);
+        assert!(outer_usage.allocations >= 2 && outer_usage.peak >= 1 << 20, "{:?}", outer_usage);
+    }
+}
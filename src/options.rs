@@ -0,0 +1,162 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::collections::BTreeMap;
+
+/// The type of value an option takes, which determines how it's parsed and validated.
+#[derive(Copy, Clone, Debug)]
+pub enum Kind {
+    /// A non-negative integer no smaller than `min`
+    Number{ min: usize },
+    /// A non-empty, comma-separated list of non-negative integers, e.g. `0,3,6`
+    Numbers,
+}
+
+impl Kind {
+    fn metavar(&self) -> &'static str {
+        match self {
+            Kind::Number{..} => "N",
+            Kind::Numbers => "N,N,...",
+        }
+    }
+
+    fn parse(&self, value: &str) -> Result<Value> {
+        match self {
+            Kind::Number{ min } => {
+                let n: usize = value.parse().context("Should be a non-negative integer")?;
+                ensure!(n >= *min, "Should be at least {}", min);
+                Ok(Value::Number(n))
+            },
+            Kind::Numbers => Ok(Value::Numbers(value.split(',').map(|n| n.trim().parse()).collect::<Result<_, _>>()
+                .context("Should be a comma-separated list of non-negative integers")?)),
+        }
+    }
+}
+
+/// A named, typed flag a day accepts, such as `--rounds 100`.
+#[derive(Debug)]
+pub struct Opt {
+    pub name: &'static str,
+    pub kind: Kind,
+    /// Shown in the help text; the day applies its own default when the option isn't set
+    pub default: Option<&'static str>,
+    pub help: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(usize),
+    Numbers(Vec<usize>),
+}
+
+/// The options set on the command line, parsed and validated against a day's Opts.
+#[derive(Debug, Default)]
+pub struct Options(BTreeMap<&'static str, Value>);
+
+impl Options {
+    /// Parses `--name VALUE` or `--name=VALUE` arguments, rejecting any that aren't in `opts`.
+    pub fn parse(opts: &[Opt], args: &[String]) -> Result<Options> {
+        let mut options = BTreeMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.strip_prefix("--") {
+                Some(flag) => match flag.find('=') {
+                    Some(i) => (&flag[..i], Some(flag[i+1..].to_string())),
+                    None => (flag, None),
+                },
+                None => bail!("Unexpected argument: {}", arg),
+            };
+            let opt = match opts.iter().find(|o| o.name == name) {
+                Some(opt) => opt,
+                None if opts.is_empty() => bail!("Unknown option --{}; this day takes no options", name),
+                None => bail!("Unknown option --{}; expected one of {}", name,
+                              opts.iter().map(|o| format!("--{}", o.name)).collect::<Vec<_>>().join(", ")),
+            };
+            let value = match inline_value.or_else(|| args.next().cloned()) {
+                Some(value) => value,
+                None => bail!("--{} requires a value", name),
+            };
+            let value = opt.kind.parse(&value).with_context(|| format!("Invalid --{}: {}", name, value))?;
+            ensure!(options.insert(opt.name, value).is_none(), "--{} was set more than once", name);
+        }
+        Ok(Options(options))
+    }
+
+    pub fn number(&self, name: &str) -> Option<usize> {
+        match self.0.get(name) {
+            Some(Value::Number(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn numbers(&self, name: &str) -> Option<&[usize]> {
+        match self.0.get(name) {
+            Some(Value::Numbers(ns)) => Some(ns),
+            _ => None,
+        }
+    }
+}
+
+/// Describes each option, one per line.
+pub fn help(opts: &[Opt]) -> String {
+    let flags: Vec<_> = opts.iter().map(|o| format!("--{} {}", o.name, o.kind.metavar())).collect();
+    let width = flags.iter().map(|f| f.len()).max().unwrap_or(0);
+    opts.iter().zip(flags.iter()).map(|(opt, flag)| {
+        let default = opt.default.map(|d| format!(" (default {})", d)).unwrap_or_default();
+        format!("  {:<w$}  {}{}", flag, opt.help, default, w = width)
+    }).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTS: &[Opt] = &[
+        Opt{ name: "rounds", kind: Kind::Number{ min: 1 }, default: Some("100"), help: "How many rounds to play" },
+        Opt{ name: "target", kind: Kind::Number{ min: 0 }, default: None, help: "The sum to look for" },
+        Opt{ name: "seed", kind: Kind::Numbers, default: None, help: "The starting numbers" },
+    ];
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(OPTS, &args.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn typed() {
+        let options = parse(&["--rounds", "10", "--target=2020", "--seed", "0,3,6"]).unwrap();
+        assert_eq!(options.number("rounds"), Some(10));
+        assert_eq!(options.number("target"), Some(2020));
+        assert_eq!(options.numbers("seed"), Some(&[0, 3, 6][..]));
+        assert_eq!(options.numbers("rounds"), None);
+
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.number("rounds"), None);
+    }
+
+    parameterized_test::create!{invalid, (args, message), {
+        let error = format!("{:#}", parse(args).unwrap_err());
+        assert!(error.contains(message), "{}", error);
+    }}
+    invalid!{
+        unknown: (&["--depth", "5"], "Unknown option --depth; expected one of --rounds, --target, --seed"),
+        positional: (&["5"], "Unexpected argument: 5"),
+        missing_value: (&["--rounds"], "--rounds requires a value"),
+        not_a_number: (&["--rounds", "many"], "Invalid --rounds: many: Should be a non-negative integer"),
+        negative: (&["--target=-5"], "Invalid --target: -5"),
+        too_small: (&["--rounds", "0"], "Should be at least 1"),
+        bad_list: (&["--seed", "0,x"], "Should be a comma-separated list"),
+        repeated: (&["--target", "1", "--target", "2"], "--target was set more than once"),
+    }
+
+    #[test]
+    fn no_options() {
+        let error = Options::parse(&[], &["--depth".to_string(), "5".to_string()]).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown option --depth; this day takes no options");
+    }
+
+    #[test]
+    fn help_text() {
+        assert_eq!(help(OPTS), "  \
+  --rounds N      How many rounds to play (default 100)
+  --target N      The sum to look for
+  --seed N,N,...  The starting numbers");
+    }
+}
@@ -1,9 +1,12 @@
 use anyhow::{Context, Result};
 use regex::{Captures, Regex};
 
+#[macro_export]
 macro_rules! static_regex {
   ($pattern:literal) => {{
-    lazy_static! { static ref RE: regex::Regex = regex::Regex::new($pattern).unwrap(); }
+    $crate::__private::lazy_static! {
+      static ref RE: $crate::__private::Regex = $crate::__private::Regex::new($pattern).unwrap();
+    }
     &*RE
   }}
 }
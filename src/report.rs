@@ -0,0 +1,101 @@
+use crate::console::Frame;
+use crate::runner::{DayReport, Part};
+use anyhow::Result;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; border-bottom: 1px solid #ddd; text-align: left; }
+td.time { text-align: right; }
+td.error { color: #cd3131; }
+pre.frame { background: #1e1e1e; color: #d4d4d4; padding: 1em; line-height: 1; display: inline-block; }";
+
+/// Renders a self-contained HTML page with each day's answers and timings, followed by the last
+/// frame each day displayed, if any.
+pub fn html(results: &[(u32, Result<DayReport>, Option<Frame>)]) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Advent of Code 2020</title>\n");
+    out.push_str(&format!("<style>\n{}\n</style>\n</head>\n<body>\n<h1>Advent of Code 2020</h1>\n", STYLE));
+
+    out.push_str("<table>\n<tr><th>Day</th><th>Part 1</th><th>Time</th><th>Part 2</th><th>Time</th></tr>\n");
+    for (day, result, frame) in results {
+        let day_cell = match frame {
+            Some(_) => format!("<a href=\"#day{:02}\">{}</a>", day, day),
+            None => day.to_string(),
+        };
+        match result {
+            Ok(report) => out.push_str(&format!("<tr><td>{}</td>{}{}</tr>\n",
+                                                day_cell, part_cells(&report.part1), part_cells(&report.part2))),
+            Err(e) => out.push_str(&format!("<tr><td>{}</td><td class=\"error\" colspan=\"4\">error: {}</td></tr>\n",
+                                            day_cell, escape(&format!("{:#}", e)))),
+        }
+    }
+    out.push_str("</table>\n");
+
+    for (day, _, frame) in results {
+        if let Some(frame) = frame {
+            out.push_str(&format!("<h2 id=\"day{:02}\">Day {}</h2>\n<pre class=\"frame\">{}</pre>\n",
+                                  day, day, render_frame(frame)));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn part_cells(part: &Part) -> String {
+    let class = if part.answer.is_ok() { "" } else { " class=\"error\"" };
+    format!("<td{}>{}</td><td class=\"time\">{}</td>", class, escape(&part.describe()), escape(&part.describe_time()))
+}
+
+// Like Console::interactive_display(), colorized characters are drawn as solid blocks
+fn render_frame(frame: &Frame) -> String {
+    let mut out = String::new();
+    let mut chars = frame.text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut run = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            run += 1;
+        }
+        match frame.colors.get(&c) {
+            Some(color) => out.push_str(&format!("<span style=\"color:{}\">{}</span>", color.css(), "█".repeat(run))),
+            None => out.push_str(&escape(&c.to_string().repeat(run))),
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::Color;
+    use std::time::Duration;
+
+    #[test]
+    fn frames() {
+        let frame = Frame{ text: "LL#.\n<>".into(), colors: vec!(('L', Color::BLUE), ('#', Color::YELLOW)).into_iter().collect() };
+        assert_eq!(render_frame(&frame),
+                   "<span style=\"color:#2472c8\">██</span><span style=\"color:#e5e510\">█</span>.\n&lt;&gt;");
+    }
+
+    #[test]
+    fn page() {
+        let part = |answer: Result<crate::solution::Answer>| Part{ answer, elapsed: Duration::from_millis(3), memory: None, cached: false, details: Vec::new() };
+        let frame = Frame{ text: "#".into(), colors: Default::default() };
+        let results = vec!(
+            (11, Ok(DayReport{ part1: part(Ok(37.into())), part2: part(Ok(26.into())) }), Some(frame)),
+            (12, Ok(DayReport{ part1: part(Ok(25.into())), part2: part(Err(anyhow::anyhow!("<oops>"))) }), None),
+            (13, Err(anyhow::anyhow!("No input")), None),
+        );
+        let page = html(&results);
+        assert!(page.contains("<tr><td><a href=\"#day11\">11</a></td><td>37</td><td class=\"time\">3.0ms</td>\
+                               <td>26</td><td class=\"time\">3.0ms</td></tr>"), "{}", page);
+        assert!(page.contains("<td class=\"error\">error: &lt;oops&gt;</td>"), "{}", page);
+        assert!(page.contains("<tr><td>13</td><td class=\"error\" colspan=\"4\">error: No input</td></tr>"), "{}", page);
+        assert!(page.contains("<h2 id=\"day11\">Day 11</h2>\n<pre class=\"frame\">#</pre>"), "{}", page);
+    }
+}
@@ -0,0 +1,429 @@
+use crate::answer_cache::{self, AnswerCache};
+use crate::budget::{self, TimedOut};
+use crate::console::Console;
+use crate::input::{self, Source};
+use crate::json;
+use crate::memory::{self, Usage};
+use crate::solution::{Answer, Day, Failure, Solution};
+use crate::trace;
+use anyhow::{bail, ensure, Context, Result};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// The outcome of solving one part of a day's puzzle.
+pub struct Part {
+    pub answer: Result<Answer>,
+    pub elapsed: Duration,
+    /// Only measured when the counting allocator is enabled
+    pub memory: Option<Usage>,
+    /// Whether the answer came from the answer cache, rather than being solved
+    pub cached: bool,
+    /// What the part passed to Console::detail(); cached parts have none
+    pub details: Vec<String>,
+}
+
+impl Part {
+    fn run(f: impl FnOnce() -> Result<Answer>) -> Part {
+        let start = Instant::now();
+        let scope = memory::Scope::start();
+        let (answer, details) = Console::record_details(f);
+        let elapsed = start.elapsed();
+        Part{ answer, elapsed, memory: if memory::ENABLED { Some(scope.usage()) } else { None }, cached: false, details }
+    }
+
+    fn cached(answer: Answer) -> Part {
+        Part{ answer: Ok(answer), elapsed: Duration::ZERO, memory: None, cached: true, details: Vec::new() }
+    }
+
+    pub fn describe_time(&self) -> String {
+        if self.cached { "cached".to_string() } else { format!("{:.1?}", self.elapsed) }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.answer {
+            Ok(answer) => answer.to_string(),
+            // The error's context describes how far the part got before giving up
+            Err(e) if TimedOut::caused(e) => format!("timed out: {}", e),
+            Err(e) => format!("error: {}", e),
+        }
+    }
+}
+
+pub struct DayReport {
+    pub part1: Part,
+    pub part2: Part,
+}
+
+/// Solves a day's parts one at a time, using cached answers where possible. The input is only
+/// parsed once a part actually needs solving.
+pub struct Solver<'a> {
+    day: &'a Day,
+    input: String,
+    args: &'a [String],
+    key: String,
+    cache: Option<AnswerCache>,
+    solution: Option<Box<dyn Solution>>,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(day: &'a Day, source: &Source, args: &'a [String]) -> Result<Solver<'a>> {
+        let input = input::load(day.number, source)?;
+        let key = answer_cache::input_key(&input, args);
+        Ok(Solver{ day, input, args, key, cache: AnswerCache::open(), solution: None })
+    }
+
+    /// Solves part 1 or 2. Returns an error only if the input cannot be parsed; failing to solve
+    /// the part is recorded in the Part.
+    pub fn part(&mut self, part: u32) -> Result<Part> {
+        if let Some(answer) = self.cache.as_ref().and_then(|c| c.get(self.day.number, part, &self.key)) {
+            return Ok(Part::cached(answer));
+        }
+        if self.solution.is_none() {
+            let _span = trace::Span::enter("Parse");
+            self.solution = Some(self.day.load(&self.input, self.args)?);
+        }
+        let solution = self.solution.as_ref().expect("Just loaded");
+        let mut result = Part::run(|| budget::budgeted(self.day.number, || {
+            let _span = trace::Span::enter(format!("Part {}", part));
+            if part == 1 { solution.part1() } else { solution.part2() }
+        }));
+        // Running out of budget means no answer was found in time, not that the solution is broken
+        result.answer = result.answer.map_err(|e|
+            if TimedOut::caused(&e) && Failure::kind(&e).is_none() { Failure::no_solution(e) } else { e });
+        if let (Some(cache), Ok(answer)) = (&self.cache, &result.answer) {
+            // Failing to cache an answer shouldn't fail the run
+            if let Err(e) = cache.put(self.day.number, part, &self.key, answer) {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Loads the day's input and solves both parts. Returns an error only if the input cannot be read
+/// or parsed; failures solving either part are recorded in the report.
+pub fn run_day(day: &Day, source: &Source, args: &[String]) -> Result<DayReport> {
+    let _span = trace::Span::enter(format!("Day {}", day.number));
+    let mut solver = Solver::new(day, source, args)?;
+    let part1 = solver.part(1)?;
+    let part2 = solver.part(2)?;
+    Ok(DayReport{ part1, part2 })
+}
+
+/// Calls `work` for each index in `0..count` on up to `jobs` threads, and passes each result to
+/// `emit` in index order as soon as it and all earlier results are available.
+pub fn in_order<T: Send>(count: usize, jobs: usize, work: impl Fn(usize) -> T + Sync, mut emit: impl FnMut(usize, T)) {
+    if jobs <= 1 {
+        for i in 0..count {
+            emit(i, work(i));
+        }
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(count) {
+            let sender = sender.clone();
+            let (next, work) = (&next, &work);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= count || sender.send((i, work(i))).is_err() { break; }
+            });
+        }
+        drop(sender); // so the receiver completes once all workers are done
+
+        let mut pending = BTreeMap::new();
+        let mut emitted = 0;
+        for (i, result) in receiver {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&emitted) {
+                emit(emitted, result);
+                emitted += 1;
+            }
+        }
+    });
+}
+
+/// Summarizes which days failed, if any. The error is marked as bad input if any day couldn't be
+/// loaded, is unmarked (i.e. internal) if any part failed unexpectedly, and is otherwise marked as
+/// no solution.
+pub fn failures(results: &[(u32, Result<DayReport>)]) -> Option<anyhow::Error> {
+    let unloaded: Vec<_> = results.iter().filter(|(_, r)| r.is_err()).map(|(day, _)| day.to_string()).collect();
+    if !unloaded.is_empty() {
+        return Some(Failure::bad_input(anyhow::anyhow!("Failed to load day {}", unloaded.join(", "))));
+    }
+    let unsolved: Vec<_> = results.iter()
+        .filter_map(|(day, r)| r.as_ref().ok().map(|r| (day, r)))
+        .flat_map(|(day, r)| vec!((day, 1, &r.part1), (day, 2, &r.part2)))
+        .filter_map(|(day, part, result)| result.answer.as_ref().err()
+            .map(|e| (format!("day {} part {}", day, part), Failure::kind(e))))
+        .collect();
+    let failed: Vec<_> = unsolved.iter().filter(|(_, kind)| kind.is_none()).map(|(p, _)| p.as_str()).collect();
+    if !failed.is_empty() {
+        return Some(anyhow::anyhow!("Failed to solve {}", failed.join(", ")));
+    }
+    if !unsolved.is_empty() {
+        let unsolved: Vec<_> = unsolved.iter().map(|(p, _)| p.as_str()).collect();
+        return Some(Failure::no_solution(anyhow::anyhow!("No solution for {}", unsolved.join(", "))));
+    }
+    None
+}
+
+/// One JSON record per part, each holding the answer or error, how long the part took and, if
+/// measured, what it allocated.
+pub fn json_records(day: u32, result: &Result<DayReport>) -> Vec<json::Value> {
+    match result {
+        Ok(report) => vec!((1, &report.part1), (2, &report.part2)).into_iter().map(|(part, result)| {
+            let (answer, error) = match &result.answer {
+                Ok(Answer::Int(n)) => (json::Value::Int(*n), None),
+                Ok(Answer::Text(s)) => (json::Value::from(s.as_str()), None),
+                Ok(Answer::None) => (json::Value::Null, None),
+                Err(e) => (json::Value::Null, Some(format!("{:#}", e))),
+            };
+            let memory = result.memory.map(|m| json_object!(
+                "allocations" => m.allocations as u64, "bytes" => m.bytes as u64, "peak_bytes" => m.peak as u64));
+            json_object!(
+                "day" => day, "part" => part as u32, "answer" => answer,
+                "duration_ns" => result.elapsed.as_nanos() as u64, "memory" => memory, "cached" => result.cached,
+                "error" => error)
+        }).collect(),
+        Err(e) => vec!(1_u32, 2).into_iter().map(|part| json_object!(
+            "day" => day, "part" => part, "answer" => json::Value::Null,
+            "duration_ns" => json::Value::Null, "memory" => json::Value::Null, "cached" => false,
+            "error" => format!("{:#}", e))).collect(),
+    }
+}
+
+/// Selects the registered days matching a spec such as `all`, `5`, `1-10` or `1-3,7`.
+pub fn select<'a>(days: &'a [Day], spec: &str) -> Result<Vec<&'a Day>> {
+    select_days(days, spec).map_err(Failure::bad_input)
+}
+
+fn select_days<'a>(days: &'a [Day], spec: &str) -> Result<Vec<&'a Day>> {
+    if spec == "all" {
+        return Ok(days.iter().collect());
+    }
+    let mut selected = Vec::new();
+    for range in spec.split(',') {
+        let (start, end) = match range.find('-') {
+            Some(i) => (&range[..i], &range[i+1..]),
+            None => (range, range),
+        };
+        let parse = |n: &str| n.trim().parse::<u32>()
+            .with_context(|| format!("Should be a natural number, or a range like 1-10: {}", range));
+        let (start, end) = (parse(start)?, parse(end)?);
+        ensure!(start <= end, "Invalid range: {}", range);
+        for n in start..=end {
+            match days.iter().find(|d| d.number == n) {
+                Some(day) => selected.push(day),
+                None => bail!("Day {} hasn't happened yet.", n),
+            }
+        }
+    }
+    Ok(selected)
+}
+
+/// Renders one row per day, with each part's answer, how long it took and, if measured, the most
+/// memory it had allocated at once.
+pub fn summary_table(results: &[(u32, Result<DayReport>)]) -> String {
+    let measured = results.iter().any(|(_, r)| r.as_ref().map(|r| r.part1.memory.is_some()).unwrap_or(false));
+    let part_columns = |part: &Part| {
+        let mut cells = vec!(part.describe(), part.describe_time());
+        if measured {
+            cells.push(part.memory.map(|m| memory::format_bytes(m.peak)).unwrap_or_default());
+        }
+        cells
+    };
+    let rows: Vec<Vec<String>> = results.iter().map(|(day, result)| match result {
+        Ok(report) => std::iter::once(day.to_string())
+            .chain(part_columns(&report.part1)).chain(part_columns(&report.part2)).collect(),
+        Err(e) => vec!(day.to_string(), format!("error: {:#}", e)),
+    }).collect();
+
+    let header = if measured {
+        vec!("Day", "Part 1", "Time", "Peak", "Part 2", "Time", "Peak")
+    } else {
+        vec!("Day", "Part 1", "Time", "Part 2", "Time")
+    };
+    let mut widths: Vec<_> = header.iter().map(|h| h.len()).collect();
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        let mut line = format!("{:>w$}", cells[0], w = widths[0]);
+        for (i, cell) in cells.iter().enumerate().skip(1) {
+            // Left-align answers, right-align times and sizes
+            if header[i].starts_with("Part") {
+                line.push_str(&format!("  {:<w$}", cell, w = widths[i]));
+            } else {
+                line.push_str(&format!("  {:>w$}", cell, w = widths[i]));
+            }
+        }
+        line.trim_end().to_string()
+    };
+
+    let mut out = vec!(format_row(&header));
+    out.push(widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>().join("  "));
+    for row in rows.iter() {
+        out.push(format_row(&row.iter().map(|c| c.as_str()).collect::<Vec<_>>()));
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::{self, FailureKind, Solution};
+
+    struct Fake;
+    impl Solution for Fake {
+        fn parse(_: &str) -> Result<Self> { Ok(Fake) }
+        fn part1(&self) -> Result<Answer> { Ok(12345.into()) }
+        fn part2(&self) -> Result<Answer> { bail!("oops") }
+    }
+
+    static DAYS: &[Day] = &[
+        Day{ number: 1, parse: solution::parse::<Fake>, options: Fake::options, generate: Fake::generate },
+        Day{ number: 2, parse: solution::parse::<Fake>, options: Fake::options, generate: Fake::generate },
+        Day{ number: 3, parse: solution::parse::<Fake>, options: Fake::options, generate: Fake::generate },
+        Day{ number: 5, parse: solution::parse::<Fake>, options: Fake::options, generate: Fake::generate },
+    ];
+
+    fn numbers(days: Vec<&Day>) -> Vec<u32> {
+        days.iter().map(|d| d.number).collect()
+    }
+
+    parameterized_test::create!{selections, (spec, expected), {
+        assert_eq!(numbers(select(DAYS, spec).unwrap()), expected);
+    }}
+    selections!{
+        all: ("all", vec!(1, 2, 3, 5)),
+        single: ("2", vec!(2)),
+        range: ("1-3", vec!(1, 2, 3)),
+        list: ("5,1-2", vec!(5, 1, 2)),
+    }
+
+    parameterized_test::create!{bad_selections, spec, {
+        assert!(select(DAYS, spec).is_err());
+    }}
+    bad_selections!{
+        missing_day: "4",
+        missing_in_range: "1-5",
+        backwards: "3-1",
+        not_a_number: "one",
+    }
+
+    parameterized_test::create!{ordered, jobs, {
+        let mut results = Vec::new();
+        in_order(10, jobs, |i| {
+            // later indices finish first
+            std::thread::sleep(Duration::from_millis(10 - i as u64));
+            i * i
+        }, |i, r| results.push((i, r)));
+        assert_eq!(results, (0..10).map(|i| (i, i * i)).collect::<Vec<_>>());
+    }}
+    ordered!{
+        serial: 1,
+        parallel: 4,
+        more_jobs_than_work: 20,
+    }
+
+    #[test]
+    fn details() {
+        let part = Part::run(|| {
+            Console::detail("outer");
+            let (_, inner) = Console::record_details(|| Console::detail("inner"));
+            assert_eq!(inner, vec!("inner"));
+            Ok(5.into())
+        });
+        assert_eq!(part.details, vec!("outer"));
+        // Outside of a Part, details are discarded
+        Console::detail("ignored");
+        assert!(Part::run(|| Ok(6.into())).details.is_empty());
+    }
+
+    #[test]
+    fn failure_summary() {
+        let part = |answer: Result<Answer>| Part{ answer, elapsed: Duration::from_millis(1), memory: None, cached: false, details: Vec::new() };
+        let solved = || Ok(DayReport{ part1: part(Ok(1.into())), part2: part(Ok(2.into())) });
+        let unsolved = || Ok(DayReport{ part1: part(Ok(1.into())), part2: part(Err(Failure::no_solution(anyhow::anyhow!("none")))) });
+        let broken = || Ok(DayReport{ part1: part(Err(anyhow::anyhow!("oops"))), part2: part(Ok(2.into())) });
+
+        assert!(failures(&[(1, solved())]).is_none());
+        let error = failures(&[(1, solved()), (2, unsolved()), (3, unsolved())]).unwrap();
+        assert_eq!(error.to_string(), "No solution for day 2 part 2, day 3 part 2");
+        assert_eq!(Failure::kind(&error), Some(FailureKind::NoSolution));
+        let error = failures(&[(1, unsolved()), (2, broken())]).unwrap();
+        assert_eq!(error.to_string(), "Failed to solve day 2 part 1");
+        assert_eq!(Failure::kind(&error), None);
+        let error = failures(&[(1, unsolved()), (2, Err(anyhow::anyhow!("bad input")))]).unwrap();
+        assert_eq!(error.to_string(), "Failed to load day 2");
+        assert_eq!(Failure::kind(&error), Some(FailureKind::BadInput));
+    }
+
+    parameterized_test::create!{descriptions, (error, expected), {
+        let part = Part{ answer: Err(error.context("Searched 100 candidates")), elapsed: Duration::ZERO, memory: None, cached: false, details: Vec::new() };
+        assert_eq!(part.describe(), expected);
+    }}
+    descriptions!{
+        failed: (anyhow::anyhow!("No candidates left"), "error: Searched 100 candidates"),
+        timed_out: (anyhow::Error::new(TimedOut(Duration::from_secs(1))), "timed out: Searched 100 candidates"),
+    }
+
+    #[test]
+    fn json() {
+        let part = |answer: Result<Answer>, millis| Part{ answer, elapsed: Duration::from_millis(millis), memory: None, cached: false, details: Vec::new() };
+        let usage = Usage{ allocations: 3, bytes: 2048, peak: 1024 };
+        let report = Ok(DayReport{
+            part1: Part{ memory: Some(usage), ..part(Ok("abc".into()), 2) },
+            part2: part(Err(anyhow::anyhow!("oops")), 30) });
+        let records: Vec<_> = json_records(4, &report).iter().map(|r| r.to_string()).collect();
+        assert_eq!(records, vec!(
+            concat!(r#"{"day":4,"part":1,"answer":"abc","duration_ns":2000000,"#,
+                    r#""memory":{"allocations":3,"bytes":2048,"peak_bytes":1024},"cached":false,"error":null}"#),
+            r#"{"day":4,"part":2,"answer":null,"duration_ns":30000000,"memory":null,"cached":false,"error":"oops"}"#));
+
+        let records: Vec<_> = json_records(4, &Err(anyhow::anyhow!("bad input"))).iter()
+            .map(|r| r.to_string()).collect();
+        assert_eq!(records, vec!(
+            r#"{"day":4,"part":1,"answer":null,"duration_ns":null,"memory":null,"cached":false,"error":"bad input"}"#,
+            r#"{"day":4,"part":2,"answer":null,"duration_ns":null,"memory":null,"cached":false,"error":"bad input"}"#));
+    }
+
+    #[test]
+    fn table() {
+        let part = |answer: Result<Answer>, millis| Part{ answer, elapsed: Duration::from_millis(millis), memory: None, cached: false, details: Vec::new() };
+        let results = vec!(
+            (1, Ok(DayReport{
+                part1: part(Ok(12345.into()), 2), part2: part(Err(anyhow::anyhow!("oops")), 30) })),
+            (2, Err(anyhow::anyhow!("bad input"))),
+            (3, Ok(DayReport{ part1: Part::cached(7.into()), part2: Part::cached(Answer::None) })),
+        );
+        assert_eq!(summary_table(&results), "\
+Day  Part 1              Time  Part 2         Time
+---  ----------------  ------  -----------  ------
+  1  12345              2.0ms  error: oops  30.0ms
+  2  error: bad input
+  3  7                 cached  -            cached");
+    }
+
+    #[test]
+    fn table_with_memory() {
+        let part = |answer: Result<Answer>, peak| Part{
+            answer, elapsed: Duration::from_millis(2), memory: Some(Usage{ allocations: 1, bytes: peak, peak }),
+            cached: false, details: Vec::new() };
+        let results = vec!(
+            (1, Ok(DayReport{ part1: part(Ok(12345.into()), 100), part2: part(Ok(6.into()), 240_000_000) })),
+        );
+        assert_eq!(summary_table(&results), "\
+Day  Part 1   Time  Peak  Part 2   Time      Peak
+---  ------  -----  ----  ------  -----  --------
+  1  12345   2.0ms  100B  6       2.0ms  228.9MiB");
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::path::{Path, PathBuf};
+
+const TEMPLATE: &str = r#"use anyhow::{bail, Result};
+use crate::solution::{Answer, Solution};
+
+pub struct NAME(Vec<String>);
+
+impl Solution for NAME {
+    fn parse(input: &str) -> Result<Self> {
+        Ok(NAME(parse_data(input)?))
+    }
+
+    fn part1(&self) -> Result<Answer> {
+        bail!("Part 1 isn't solved yet")
+    }
+
+    fn part2(&self) -> Result<Answer> {
+        bail!("Part 2 isn't solved yet")
+    }
+}
+
+fn parse_data(input: &str) -> Result<Vec<String>> {
+    Ok(input.trim().lines().map(|l| l.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    parameterized_test::create!{example, (input, lines), {
+        assert_eq!(parse_data(input).unwrap().len(), lines);
+    }}
+    example!{
+        a: (include_str!("../data/dayNN_example.txt"), 0),
+    }
+
+    #[test]
+    fn parse_file() {
+        parse_data(include_str!("../data/dayNN.txt")).unwrap();
+    }
+}
+"#;
+
+const SIDECAR: &str = "# The answers data/dayNN_example.txt should produce, one `PART ANSWER` line per part, e.g.
+# 1 42
+";
+
+/// The source of a new day's module, with a Solution named `name` that parses its input into lines.
+pub fn module(day: u32, name: &str) -> String {
+    TEMPLATE.replace("NN", &format!("{:02}", day)).replace("NAME", name)
+}
+
+/// Adds `day => aocNN::name` to the days! invocation in lib.rs, keeping the days in order.
+pub fn register(lib_rs: &str, day: u32, name: &str) -> Result<String> {
+    let mut lines: Vec<_> = lib_rs.lines().map(|l| l.to_string()).collect();
+    let start = lines.iter().position(|l| l == "days! {").context("No days! invocation found")?;
+    let end = start + lines[start..].iter().position(|l| l == "}").context("Unterminated days! invocation")?;
+
+    let mut insert_at = end;
+    for (i, line) in lines.iter().enumerate().take(end).skip(start+1) {
+        let registered: u32 = line.split("=>").next().and_then(|n| n.trim().parse().ok())
+            .with_context(|| format!("Unexpected line in days! invocation: {}", line))?;
+        ensure!(registered != day, "Day {} is already registered", day);
+        if registered > day {
+            insert_at = i;
+            break;
+        }
+    }
+    lines.insert(insert_at, format!("    {} => aoc{:02}::{},", day, day, name));
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Generates src/aocNN.rs, registers it in src/lib.rs, and creates placeholder input files and an
+/// example answers sidecar under `root`. Returns the files created or modified.
+pub fn create(root: &Path, day: u32, name: &str) -> Result<Vec<PathBuf>> {
+    ensure!((1..=25).contains(&day), "Advent only has 25 days: {}", day);
+    let source = root.join(format!("src/aoc{:02}.rs", day));
+    if source.exists() {
+        bail!("{} already exists", source.display());
+    }
+    let lib_rs = root.join("src/lib.rs");
+    let registered = register(&std::fs::read_to_string(&lib_rs)
+        .with_context(|| format!("Failed to read {}", lib_rs.display()))?, day, name)?;
+
+    let mut written = Vec::new();
+    std::fs::write(&source, module(day, name)).with_context(|| format!("Failed to write {}", source.display()))?;
+    written.push(source);
+    std::fs::write(&lib_rs, registered).with_context(|| format!("Failed to write {}", lib_rs.display()))?;
+    written.push(lib_rs);
+    // Existing inputs are left alone. An empty data/dayNN.txt is treated as missing by the input
+    // cache, so the real input is still downloaded once a session token is available.
+    let placeholders = [
+        (format!("data/day{:02}.txt", day), String::new()),
+        (format!("data/day{:02}_example.txt", day), String::new()),
+        (format!("data/day{:02}_example.answers", day), SIDECAR.replace("NN", &format!("{:02}", day))),
+    ];
+    for (data, contents) in placeholders.iter() {
+        let path = root.join(data);
+        if !path.exists() {
+            std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB_RS: &str = "mod console;\n\ndays! {\n    1 => aoc01::Expenses,\n    3 => aoc03::Landscape,\n}\n\nfn helper() {\n}\n";
+
+    parameterized_test::create!{registration, (day, expected), {
+        let lib_rs = register(LIB_RS, day, "Puzzle").unwrap();
+        let days: Vec<_> = lib_rs.lines().filter(|l| l.contains("=>")).map(|l| l.trim()).collect();
+        assert_eq!(days, expected);
+        assert!(lib_rs.ends_with("}\n\nfn helper() {\n}\n"));
+    }}
+    registration!{
+        middle: (2, vec!("1 => aoc01::Expenses,", "2 => aoc02::Puzzle,", "3 => aoc03::Landscape,")),
+        last: (12, vec!("1 => aoc01::Expenses,", "3 => aoc03::Landscape,", "12 => aoc12::Puzzle,")),
+    }
+
+    #[test]
+    fn bad_registration() {
+        assert!(register(LIB_RS, 3, "Puzzle").is_err());
+        assert!(register("fn helper() {}", 3, "Puzzle").is_err());
+    }
+
+    #[test]
+    fn real_layout() {
+        // Removing a day from the real lib.rs and scaffolding it again restores the file
+        let lib_rs = include_str!("lib.rs");
+        let line = "    12 => aoc12::Navigation,\n";
+        assert!(lib_rs.contains(line));
+        let root = std::env::temp_dir().join(format!("advent-2020-scaffold-real-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), lib_rs.replace(line, "")).unwrap();
+
+        create(&root, 12, "Navigation").unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap(), lib_rs);
+        assert!(register(lib_rs, 12, "Navigation").is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn generated_module() {
+        let source = module(7, "Luggage");
+        assert!(source.contains("impl Solution for Luggage {"));
+        assert!(source.contains("include_str!(\"../data/day07_example.txt\")"));
+        assert!(source.contains("include_str!(\"../data/day07.txt\")"));
+        assert!(!source.contains("NAME") && !source.contains("NN"));
+    }
+
+    #[test]
+    fn create_files() {
+        let root = std::env::temp_dir().join(format!("advent-2020-scaffold-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), LIB_RS).unwrap();
+        std::fs::write(root.join("data/day02.txt"), "real input").unwrap();
+
+        let written = create(&root, 2, "Puzzle").unwrap();
+        let written: Vec<_> = written.iter().map(|p| p.strip_prefix(&root).unwrap().to_str().unwrap()).collect();
+        assert_eq!(written, vec!("src/aoc02.rs", "src/lib.rs", "data/day02_example.txt", "data/day02_example.answers"));
+        let sidecar = std::fs::read_to_string(root.join("data/day02_example.answers")).unwrap();
+        assert!(sidecar.contains("data/day02_example.txt"));
+        let (args, answers) = crate::fixtures::parse_sidecar(&sidecar).unwrap();
+        assert!(args.is_empty() && answers.is_empty());
+        assert_eq!(std::fs::read_to_string(root.join("data/day02.txt")).unwrap(), "real input");
+        assert!(std::fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("2 => aoc02::Puzzle,"));
+
+        assert!(create(&root, 2, "Puzzle").is_err());
+        assert!(create(&root, 26, "Puzzle").is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+use crate::generate::{Generated, Rng};
+use crate::options::{Opt, Options};
+use anyhow::{bail, Context, Result};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The answer to one part of a day's puzzle. Most answers are numbers, but some (e.g. Day 21's
+/// list of ingredients) are text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Int(i64),
+    Text(String),
+    /// For parts that don't have a puzzle, i.e. Day 25 part 2
+    None,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::None => write!(f, "-"),
+        }
+    }
+}
+
+macro_rules! int_answer {
+    ($($t:ty),*) => { $(
+        impl From<$t> for Answer {
+            fn from(n: $t) -> Answer {
+                Answer::Int(i64::try_from(n).expect("Answer does not fit in an i64"))
+            }
+        }
+    )* };
+}
+int_answer!(i32, i64, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer { Answer::Text(s) }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer { Answer::Text(s.to_string()) }
+}
+
+/// The kinds of failure the runner distinguishes between when reporting errors.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FailureKind {
+    /// The input, options or arguments couldn't be read or understood
+    BadInput,
+    /// The input was understood, but no answer could be found
+    NoSolution,
+}
+
+/// Marks an error as a particular kind of failure, without changing how it's displayed. Errors
+/// that aren't marked are internal errors, i.e. bugs.
+#[derive(Debug)]
+pub struct Failure {
+    kind: FailureKind,
+    error: anyhow::Error,
+}
+
+impl Failure {
+    pub fn bad_input(error: anyhow::Error) -> anyhow::Error {
+        Failure{ kind: FailureKind::BadInput, error }.into()
+    }
+
+    pub fn no_solution(error: anyhow::Error) -> anyhow::Error {
+        Failure{ kind: FailureKind::NoSolution, error }.into()
+    }
+
+    /// How the error (or any error it wraps) was marked, if at all.
+    pub fn kind(error: &anyhow::Error) -> Option<FailureKind> {
+        error.downcast_ref::<Failure>().map(|f| f.kind)
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for Failure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// A day's puzzle. The input is parsed once, and then each part is solved from the parsed data.
+pub trait Solution {
+    fn parse(input: &str) -> Result<Self> where Self: Sized;
+
+    /// The options this day accepts on the command line, which are passed to configure().
+    fn options() -> &'static [Opt] where Self: Sized { &[] }
+
+    /// Applies any options set on the command line; unset options keep their defaults.
+    fn configure(&mut self, _options: &Options) -> Result<()> { Ok(()) }
+
+    /// Creates a random input, of a day-specific `size`, along with its answers. Every day has a
+    /// generator; the default is for newly scaffolded days that don't have one yet.
+    fn generate(_rng: &mut Rng, _size: usize) -> Result<Generated> where Self: Sized {
+        bail!("No input generator")
+    }
+
+    fn part1(&self) -> Result<Answer>;
+    fn part2(&self) -> Result<Answer>;
+}
+
+/// A registered day of the advent calendar.
+pub struct Day {
+    pub number: u32,
+    pub parse: fn(&str) -> Result<Box<dyn Solution>>,
+    pub options: fn() -> &'static [Opt],
+    pub generate: fn(&mut Rng, usize) -> Result<Generated>,
+}
+
+impl Day {
+    /// Validates the command-line arguments against the day's options, then parses the input and
+    /// configures the solution with them.
+    pub fn load(&self, input: &str, args: &[String]) -> Result<Box<dyn Solution>> {
+        let options = Options::parse((self.options)(), args).map_err(Failure::bad_input)?;
+        let mut solution = (self.parse)(input)
+            .with_context(|| format!("Failed to parse day {}", self.number)).map_err(Failure::bad_input)?;
+        solution.configure(&options).map_err(Failure::bad_input)?;
+        Ok(solution)
+    }
+}
+
+/// Type-erases a Solution's parse function so days can be stored together in a registry.
+pub fn parse<S: Solution + 'static>(input: &str) -> Result<Box<dyn Solution>> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo(i64);
+    impl Solution for Echo {
+        fn parse(input: &str) -> Result<Self> { Ok(Echo(input.trim().parse()?)) }
+        fn part1(&self) -> Result<Answer> { Ok(self.0.into()) }
+        fn part2(&self) -> Result<Answer> { Ok(format!("{}!", self.0).into()) }
+    }
+
+    #[test]
+    fn type_erased() {
+        let day = Day{ number: 0, parse: parse::<Echo>, options: Echo::options, generate: Echo::generate };
+        let solution = day.load("42\n", &[]).unwrap();
+        assert_eq!(solution.part1().unwrap(), Answer::Int(42));
+        assert_eq!(solution.part2().unwrap(), Answer::Text("42!".into()));
+        let error = day.load("42", &["--times".to_string(), "5".to_string()]).err().unwrap();
+        assert_eq!(Failure::kind(&error), Some(FailureKind::BadInput));
+        let error = day.load("forty-two", &[]).err().unwrap();
+        assert_eq!(Failure::kind(&error), Some(FailureKind::BadInput));
+        assert_eq!(format!("{:#}", error), "Failed to parse day 0: invalid digit found in string");
+    }
+
+    parameterized_test::create!{display, (answer, expected), {
+        assert_eq!(answer.to_string(), expected);
+    }}
+    display!{
+        int: (Answer::from(5_usize), "5"),
+        negative: (Answer::from(-5), "-5"),
+        text: (Answer::from("a,b,c"), "a,b,c"),
+        none: (Answer::None, "-"),
+    }
+
+    #[test]
+    fn failures() {
+        let error = Failure::no_solution(anyhow::anyhow!("inner").context("outer"));
+        assert_eq!(format!("{:#}", error), "outer: inner");
+        assert_eq!(Failure::kind(&error), Some(FailureKind::NoSolution));
+        let error = error.context("wrapped");
+        assert_eq!(format!("{:#}", error), "wrapped: outer: inner");
+        assert_eq!(Failure::kind(&error), Some(FailureKind::NoSolution));
+        assert_eq!(Failure::kind(&anyhow::anyhow!("bug")), None);
+    }
+}
@@ -0,0 +1,138 @@
+/// Records `$expression` as a span named `$name` (or the expression itself) in the trace, and
+/// times it with elapsed!(). Spans nest, so spans within the expression appear beneath it.
+macro_rules! span {
+    ($expression:expr) => { span!(stringify!($expression), $expression) };
+    ($name:expr, $expression:expr) => { {
+        let _span = crate::trace::Span::enter($name);
+        elapsed!($name, $expression)
+    } };
+}
+
+use crate::json;
+use anyhow::{Context, Result};
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+lazy_static! {
+    static ref RECORDER: Recorder = Recorder::new();
+}
+
+thread_local! {
+    // Trace viewers want small integer thread IDs, which std::thread::ThreadId doesn't expose
+    static THREAD: Cell<u64> = const { Cell::new(0) };
+}
+
+fn thread_id() -> u64 {
+    THREAD.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+/// Starts recording spans for the rest of the run.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Writes every span recorded so far to `path`.
+pub fn write(path: &Path) -> Result<()> {
+    std::fs::write(path, RECORDER.to_json().to_string())
+        .with_context(|| format!("Failed to write trace {}", path.display()))
+}
+
+#[derive(Debug)]
+struct Event {
+    name: String,
+    thread: u64,
+    start: Duration,
+    duration: Duration,
+}
+
+/// Collects completed spans, timestamped relative to when the recorder was created.
+pub struct Recorder {
+    epoch: Instant,
+    events: Mutex<Vec<Event>>,
+}
+
+impl Recorder {
+    fn new() -> Recorder {
+        Recorder{ epoch: Instant::now(), events: Mutex::new(Vec::new()) }
+    }
+
+    pub fn span(&self, name: impl Into<String>) -> Span<'_> {
+        Span(Some((self, name.into(), Instant::now())))
+    }
+
+    /// The spans in Chrome's trace event format, as complete ("X") events; viewers such as
+    /// chrome://tracing or Perfetto nest them by their timestamps.
+    pub fn to_json(&self) -> json::Value {
+        let micros = |d: Duration| d.as_nanos() as f64 / 1000.0;
+        let events = self.events.lock().unwrap();
+        let events: Vec<_> = events.iter().map(|e| json_object!(
+            "name" => e.name.as_str(), "ph" => "X", "ts" => micros(e.start), "dur" => micros(e.duration),
+            "pid" => 1_u32, "tid" => e.thread)).collect();
+        json_object!("traceEvents" => events, "displayTimeUnit" => "ms")
+    }
+}
+
+/// A region of the run, recorded when dropped. Does nothing unless tracing is enabled.
+pub struct Span<'a>(Option<(&'a Recorder, String, Instant)>);
+
+impl Span<'static> {
+    pub fn enter(name: impl Into<String>) -> Span<'static> {
+        if ENABLED.load(Ordering::Relaxed) { RECORDER.span(name) } else { Span(None) }
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        if let Some((recorder, name, start)) = self.0.take() {
+            let event = Event{
+                name, thread: thread_id(), start: start - recorder.epoch, duration: start.elapsed() };
+            recorder.events.lock().unwrap().push(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let recorder = Recorder::new();
+        {
+            let _outer = recorder.span("outer");
+            for i in 0..2 {
+                let _inner = recorder.span(format!("inner {}", i));
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+        let events = recorder.events.lock().unwrap();
+        let names: Vec<_> = events.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!("inner 0", "inner 1", "outer"));
+        let outer = &events[2];
+        for inner in &events[..2] {
+            assert!(inner.start >= outer.start && inner.start + inner.duration <= outer.start + outer.duration);
+            assert_eq!(inner.thread, outer.thread);
+        }
+    }
+
+    #[test]
+    fn chrome_format() {
+        let recorder = Recorder::new();
+        recorder.events.lock().unwrap().push(Event{
+            name: "Part 1".into(), thread: 2, start: Duration::from_micros(1500), duration: Duration::from_nanos(2500) });
+        assert_eq!(recorder.to_json().to_string(), concat!(
+            r#"{"traceEvents":[{"name":"Part 1","ph":"X","ts":1500,"dur":2.5,"pid":1,"tid":2}],"#,
+            r#""displayTimeUnit":"ms"}"#));
+    }
+}
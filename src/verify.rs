@@ -0,0 +1,114 @@
+use crate::input;
+use crate::solution::Answer;
+use anyhow::{Context, Error, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The default answers file, kept with the inputs it describes.
+pub fn path() -> PathBuf {
+    input::data_dir().join("answers.txt")
+}
+
+/// Known-good answers for the real inputs, keyed by day and part.
+///
+/// Each non-blank, non-comment line is `DAY PART ANSWER`, e.g. `21 2 lmxt,rggkbpj,mxf`.
+pub struct Expected(BTreeMap<(u32, u32), String>);
+
+impl Expected {
+    pub fn load(path: &std::path::Path) -> Result<Expected> {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read expected answers {}", path.display()))?
+            .parse()
+    }
+
+    pub fn check(&self, day: u32, part: u32, answer: &Result<Answer>) -> Status {
+        match (answer, self.0.get(&(day, part))) {
+            (Err(e), _) => Status::Error(format!("{:#}", e)),
+            (Ok(actual), None) => Status::Missing(actual.to_string()),
+            (Ok(actual), Some(expected)) if actual.to_string() == *expected => Status::Pass,
+            (Ok(actual), Some(expected)) =>
+                Status::Fail{ expected: expected.clone(), actual: actual.to_string() },
+        }
+    }
+}
+
+impl FromStr for Expected {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut answers = BTreeMap::new();
+        for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let parts: Vec<_> = line.splitn(3, char::is_whitespace).collect();
+            anyhow::ensure!(parts.len() == 3, "Expected DAY PART ANSWER: {}", line);
+            let day = parts[0].parse().with_context(|| format!("Invalid day: {}", line))?;
+            let part = parts[1].parse().with_context(|| format!("Invalid part: {}", line))?;
+            anyhow::ensure!(part == 1 || part == 2, "Invalid part: {}", line);
+            answers.insert((day, part), parts[2].trim().to_string());
+        }
+        Ok(Expected(answers))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail{ expected: String, actual: String },
+    Missing(String),
+    Error(String),
+}
+
+impl Status {
+    pub fn passed(&self) -> bool {
+        *self == Status::Pass
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail{ expected, actual } => write!(f, "FAIL expected {}, got {}", expected, actual),
+            Status::Missing(actual) => write!(f, "MISSING no expected answer, got {}", actual),
+            Status::Error(e) => write!(f, "ERROR {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let expected: Expected = "# comment\n\n1 1 100\n1 2 200\n21 2 a,b c\n".parse().unwrap();
+        assert_eq!(expected.0.len(), 3);
+        assert_eq!(expected.0[&(21, 2)], "a,b c");
+
+        assert!("1 1".parse::<Expected>().is_err());
+        assert!("1 3 100".parse::<Expected>().is_err());
+        assert!("one 1 100".parse::<Expected>().is_err());
+    }
+
+    parameterized_test::create!{check, (answer, expected_status), {
+        let expected: Expected = "1 1 100\n25 2 -".parse().unwrap();
+        let (day, part, answer) = answer;
+        assert_eq!(expected.check(day, part, &answer), expected_status);
+    }}
+    check!{
+        pass: ((1, 1, Ok(Answer::Int(100))), Status::Pass),
+        none: ((25, 2, Ok(Answer::None)), Status::Pass),
+        fail: ((1, 1, Ok(Answer::Int(101))),
+               Status::Fail{ expected: "100".into(), actual: "101".into() }),
+        missing: ((1, 2, Ok(Answer::Int(5))), Status::Missing("5".into())),
+        error: ((1, 1, Err(anyhow::anyhow!("boom"))), Status::Error("boom".into())),
+    }
+
+    #[test]
+    fn checked_in_answers() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("data/answers.txt");
+        let expected = Expected::load(&path).unwrap();
+        assert_eq!(expected.0.len(), 50);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::input::{self, Source};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often watched files are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The files whose changes should re-run `day`: the `--input` file if there is one, otherwise the
+/// day's input and examples in the data directory.
+pub fn files(day: u32, source: &Source) -> Result<Vec<PathBuf>> {
+    match source {
+        Source::Default => data_files(&input::data_dir(), day),
+        Source::Stdin => bail!("Cannot watch stdin; pass an --input file instead"),
+        Source::File(path) => Ok(vec!(path.clone())),
+    }
+}
+
+/// Every `dayNN*.txt` file in `dir`, in name order.
+fn data_files(dir: &Path, day: u32) -> Result<Vec<PathBuf>> {
+    let prefix = format!("day{:02}", day);
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.starts_with(&prefix) && name.ends_with(".txt") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// The modification time and size of each file, or None if it doesn't exist
+type Snapshot = BTreeMap<PathBuf, Option<(SystemTime, u64)>>;
+
+fn snapshot(files: &[PathBuf]) -> Snapshot {
+    files.iter().map(|f| {
+        let metadata = f.metadata().ok().and_then(|m| m.modified().ok().map(|t| (t, m.len())));
+        (f.clone(), metadata)
+    }).collect()
+}
+
+/// Detects when any of a set of files is created, modified or deleted, by polling their metadata.
+pub struct Watcher<F> {
+    list: F,
+    last: Snapshot,
+}
+
+impl<F: Fn() -> Result<Vec<PathBuf>>> Watcher<F> {
+    /// Watches the files returned by `list`, which is called on every check so that newly created
+    /// files (e.g. a new example) are noticed too.
+    pub fn new(list: F) -> Result<Watcher<F>> {
+        let last = snapshot(&list()?);
+        Ok(Watcher{ list, last })
+    }
+
+    /// Whether anything changed since the last call (or since the watcher was created).
+    pub fn changed(&mut self) -> Result<bool> {
+        let current = snapshot(&(self.list)()?);
+        let changed = current != self.last;
+        self.last = current;
+        Ok(changed)
+    }
+
+    /// Blocks until something changes.
+    pub fn wait(&mut self) -> Result<()> {
+        while !self.changed()? {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_changes() {
+        let dir = std::env::temp_dir().join(format!("advent-2020-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("day19.txt"), "input").unwrap();
+        std::fs::write(dir.join("day01.txt"), "unrelated").unwrap();
+        let mut watcher = Watcher::new(|| data_files(&dir, 19)).unwrap();
+        assert!(!watcher.changed().unwrap());
+
+        std::fs::write(dir.join("day01.txt"), "still unrelated").unwrap();
+        assert!(!watcher.changed().unwrap());
+
+        std::fs::write(dir.join("day19_example2.txt"), "example").unwrap();
+        assert!(watcher.changed().unwrap());
+        assert!(!watcher.changed().unwrap());
+
+        std::fs::write(dir.join("day19_example2.txt"), "longer example").unwrap();
+        assert!(watcher.changed().unwrap());
+
+        std::fs::remove_file(dir.join("day19.txt")).unwrap();
+        assert!(watcher.changed().unwrap());
+        assert_eq!(data_files(&dir, 19).unwrap(), vec!(dir.join("day19_example2.txt")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stdin() {
+        assert!(files(1, &Source::Stdin).is_err());
+        assert_eq!(files(1, &Source::File(PathBuf::from("in.txt"))).unwrap(), vec!(PathBuf::from("in.txt")));
+    }
+}